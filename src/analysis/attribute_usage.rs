@@ -0,0 +1,128 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use serde::{Deserialize, Serialize};
+use crate::datasets::dataset_utils::{redundant_input_ids, RedundancyMapping, RedundancyType};
+use crate::vanilla_cgp::chromosome::Chromosome;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AttributeKind {
+    Original,
+    Copy,
+    NoisyCopy,
+    Noise,
+}
+
+impl Display for AttributeKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AttributeKind::Original => write!(f, "original"),
+            AttributeKind::Copy => write!(f, "copy"),
+            AttributeKind::NoisyCopy => write!(f, "noisy_copy"),
+            AttributeKind::Noise => write!(f, "noise"),
+        }
+    }
+}
+
+/// Kind and origin of every input node of a dataset bloated by `add_redundancies`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AttributeLabels {
+    kinds: Vec<AttributeKind>,
+    /// attribute of the original dataset an input is derived from; None for noise
+    sources: Vec<Option<usize>>,
+}
+
+impl AttributeLabels {
+    pub fn new(nbr_inputs: usize, mapping: &RedundancyMapping, r_type: RedundancyType) -> Self {
+        let redundant = redundant_input_ids(mapping);
+
+        let mut kinds: Vec<AttributeKind> = vec![AttributeKind::Original; nbr_inputs];
+        let mut sources: Vec<Option<usize>> = vec![None; nbr_inputs];
+
+        // inserting attributes keeps the order of the original ones,
+        // so the n-th input that is not redundant is the n-th original attribute
+        let originals: Vec<usize> = (0..nbr_inputs)
+            .filter(|input_id| !redundant.contains(input_id))
+            .collect();
+        for (original_id, input_id) in originals.iter().enumerate() {
+            sources[*input_id] = Some(original_id);
+        }
+
+        for (source, input_ids) in mapping {
+            for input_id in input_ids {
+                match r_type {
+                    RedundancyType::Copy => {
+                        kinds[*input_id] = AttributeKind::Copy;
+                        sources[*input_id] = Some(*source);
+                    }
+                    RedundancyType::NoisyCopy => {
+                        kinds[*input_id] = AttributeKind::NoisyCopy;
+                        sources[*input_id] = Some(*source);
+                    }
+                    RedundancyType::Noise => {
+                        kinds[*input_id] = AttributeKind::Noise;
+                    }
+                }
+            }
+        }
+
+        Self {
+            kinds,
+            sources,
+        }
+    }
+
+    pub fn kind(&self, input_id: usize) -> AttributeKind {
+        return self.kinds[input_id];
+    }
+
+    pub fn source(&self, input_id: usize) -> Option<usize> {
+        return self.sources[input_id];
+    }
+
+    pub fn nbr_inputs(&self) -> usize {
+        return self.kinds.len();
+    }
+}
+
+/// Which attributes the active inputs of a program are.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct AttributeUsage {
+    pub originals: usize,
+    pub copies: usize,
+    pub noisy_copies: usize,
+    pub noise: usize,
+    /// original attributes of which more than one version (the original or its copies) is used
+    pub sources_used_multiple_times: usize,
+    /// original attribute -> number of its versions that are used, for every used attribute
+    pub versions_per_source: BTreeMap<usize, usize>,
+}
+
+/// Expects the active nodes of the chromosome to be up to date, i.e. it was evaluated
+/// or `get_active_nodes_id` was called.
+pub fn attribute_usage(chromosome: &Chromosome, labels: &AttributeLabels) -> AttributeUsage {
+    let mut usage = AttributeUsage::default();
+
+    let active_inputs = chromosome.active_nodes
+        .iter()
+        .filter(|node_id| **node_id < chromosome.params.nbr_inputs);
+
+    for input_id in active_inputs {
+        match labels.kind(*input_id) {
+            AttributeKind::Original => usage.originals += 1,
+            AttributeKind::Copy => usage.copies += 1,
+            AttributeKind::NoisyCopy => usage.noisy_copies += 1,
+            AttributeKind::Noise => usage.noise += 1,
+        }
+
+        if let Some(source) = labels.source(*input_id) {
+            *usage.versions_per_source.entry(source).or_insert(0) += 1;
+        }
+    }
+
+    usage.sources_used_multiple_times = usage.versions_per_source
+        .values()
+        .filter(|versions| **versions > 1)
+        .count();
+
+    return usage;
+}
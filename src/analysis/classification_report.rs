@@ -0,0 +1,242 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ClassMetrics {
+    pub class: usize,
+    pub precision: f32,
+    pub recall: f32,
+    pub f1: f32,
+    /// number of samples of this class
+    pub support: usize,
+}
+
+/// Metrics of one set of predictions. Precision, recall and F1 are 0 if undefined.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ClassificationReport {
+    /// `confusion_matrix[true class][predicted class]`
+    pub confusion_matrix: Vec<Vec<usize>>,
+    pub classes: Vec<ClassMetrics>,
+    pub accuracy: f32,
+    /// mean recall of the classes that occur in the labels, as in `fitness_categorical_multiclass`
+    pub balanced_accuracy: f32,
+    /// multiclass Matthews correlation coefficient; 0 if undefined
+    pub mcc: f32,
+    pub macro_f1: f32,
+}
+
+impl ClassificationReport {
+    /// `nbr_classes` is raised if a label or prediction is out of range.
+    pub fn new(predictions: &Vec<usize>, labels: &Vec<usize>, nbr_classes: usize) -> Self {
+        assert_eq!(predictions.len(), labels.len());
+
+        let nbr_classes = predictions
+            .iter()
+            .chain(labels.iter())
+            .map(|class| class + 1)
+            .fold(nbr_classes, usize::max);
+
+        let mut confusion_matrix = vec![vec![0; nbr_classes]; nbr_classes];
+        for (prediction, label) in predictions.iter().zip(labels.iter()) {
+            confusion_matrix[*label][*prediction] += 1;
+        }
+
+        return ClassificationReport::from_confusion_matrix(confusion_matrix);
+    }
+
+    pub fn from_confusion_matrix(confusion_matrix: Vec<Vec<usize>>) -> Self {
+        let nbr_classes = confusion_matrix.len();
+        let total: usize = confusion_matrix.iter().flatten().sum();
+        let correct: usize = (0..nbr_classes).map(|class| confusion_matrix[class][class]).sum();
+        let true_counts: Vec<usize> = (0..nbr_classes)
+            .map(|class| confusion_matrix[class].iter().sum())
+            .collect();
+        let predicted_counts: Vec<usize> = (0..nbr_classes)
+            .map(|class| confusion_matrix.iter().map(|row| row[class]).sum())
+            .collect();
+
+        let ratio = |numerator: usize, denominator: usize| -> f32 {
+            if denominator == 0 { 0. } else { numerator as f32 / denominator as f32 }
+        };
+
+        let classes: Vec<ClassMetrics> = (0..nbr_classes)
+            .map(|class| {
+                let true_positives = confusion_matrix[class][class];
+                let precision = ratio(true_positives, predicted_counts[class]);
+                let recall = ratio(true_positives, true_counts[class]);
+                let f1 = if precision + recall > 0. {
+                    2. * precision * recall / (precision + recall)
+                } else {
+                    0.
+                };
+                ClassMetrics {
+                    class,
+                    precision,
+                    recall,
+                    f1,
+                    support: true_counts[class],
+                }
+            })
+            .collect();
+
+        let occurring: Vec<&ClassMetrics> = classes.iter().filter(|metrics| metrics.support > 0).collect();
+        let balanced_accuracy = occurring.iter().map(|metrics| metrics.recall).sum::<f32>() / occurring.len().max(1) as f32;
+        let macro_f1 = occurring.iter().map(|metrics| metrics.f1).sum::<f32>() / occurring.len().max(1) as f32;
+
+        // Gorodkin's generalization of the MCC to k classes
+        let total_f = total as f64;
+        let covariance = correct as f64 * total_f - (0..nbr_classes)
+            .map(|class| predicted_counts[class] as f64 * true_counts[class] as f64)
+            .sum::<f64>();
+        let predicted_variance = total_f * total_f - predicted_counts.iter().map(|c| (*c as f64).powi(2)).sum::<f64>();
+        let true_variance = total_f * total_f - true_counts.iter().map(|c| (*c as f64).powi(2)).sum::<f64>();
+        let mcc = if predicted_variance > 0. && true_variance > 0. {
+            (covariance / (predicted_variance * true_variance).sqrt()) as f32
+        } else {
+            0.
+        };
+
+        Self {
+            confusion_matrix,
+            classes,
+            accuracy: ratio(correct, total),
+            balanced_accuracy,
+            mcc,
+            macro_f1,
+        }
+    }
+}
+
+/// Sums confusion matrices of different sizes.
+fn pooled_confusion_matrix(reports: &[&ClassificationReport]) -> Vec<Vec<usize>> {
+    let nbr_classes = reports.iter().map(|report| report.confusion_matrix.len()).max().unwrap_or(0);
+
+    let mut confusion_matrix = vec![vec![0; nbr_classes]; nbr_classes];
+    for report in reports {
+        for (label, row) in report.confusion_matrix.iter().enumerate() {
+            for (prediction, count) in row.iter().enumerate() {
+                confusion_matrix[label][prediction] += count;
+            }
+        }
+    }
+    return confusion_matrix;
+}
+
+/// Report of the final parent of one fold, written to `run_N_report.json`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FoldReport {
+    pub train: ClassificationReport,
+    /// None if the fold has no test data
+    pub test: Option<ClassificationReport>,
+    /// original label of every class id
+    #[serde(default)]
+    pub class_labels: Vec<String>,
+}
+
+impl FoldReport {
+    pub fn path(save_path: &Path, run_id: usize) -> PathBuf {
+        return save_path.join(format!("run_{}_report.json", run_id));
+    }
+
+    pub fn save(&self, path: &Path) {
+        let mut writer = BufWriter::new(File::create(path).expect("cannot create file"));
+        serde_json::to_writer_pretty(&mut writer, self).expect("cannot write");
+        writer.flush().expect("cannot write");
+    }
+
+    pub fn load(path: &Path) -> Self {
+        let reader = BufReader::new(File::open(path).expect("cannot open file"));
+        return serde_json::from_reader(reader)
+            .unwrap_or_else(|e| panic!("invalid report {}: {}", path.display(), e));
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct MeanStd {
+    pub mean: f32,
+    pub std: f32,
+}
+
+impl MeanStd {
+    fn new(values: &[f32]) -> Self {
+        if values.is_empty() {
+            return MeanStd::default();
+        }
+        let n = values.len() as f32;
+        let mean = values.iter().sum::<f32>() / n;
+        let variance = values.iter().map(|x| (x - mean).powi(2)).sum::<f32>() / n;
+        Self {
+            mean,
+            std: variance.sqrt(),
+        }
+    }
+}
+
+/// Mean and standard deviation of the per-fold metrics.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct FoldStatistics {
+    pub accuracy: MeanStd,
+    pub balanced_accuracy: MeanStd,
+    pub mcc: MeanStd,
+    pub macro_f1: MeanStd,
+}
+
+impl FoldStatistics {
+    fn new(reports: &[&ClassificationReport]) -> Self {
+        let metric = |get: fn(&ClassificationReport) -> f32| -> MeanStd {
+            let values: Vec<f32> = reports.iter().map(|report| get(report)).collect();
+            MeanStd::new(&values)
+        };
+        Self {
+            accuracy: metric(|report| report.accuracy),
+            balanced_accuracy: metric(|report| report.balanced_accuracy),
+            mcc: metric(|report| report.mcc),
+            macro_f1: metric(|report| report.macro_f1),
+        }
+    }
+}
+
+/// All folds of a cross validation: reports of the summed confusion matrices
+/// and the spread of the metrics over the folds.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CrossValidationReport {
+    pub nbr_folds: usize,
+    /// original label of every class id
+    pub class_labels: Vec<String>,
+    pub train: ClassificationReport,
+    pub test: Option<ClassificationReport>,
+    pub train_folds: FoldStatistics,
+    pub test_folds: FoldStatistics,
+}
+
+impl CrossValidationReport {
+    pub fn new(folds: &Vec<FoldReport>) -> Self {
+        let train: Vec<&ClassificationReport> = folds.iter().map(|fold| &fold.train).collect();
+        let test: Vec<&ClassificationReport> = folds.iter().filter_map(|fold| fold.test.as_ref()).collect();
+
+        Self {
+            nbr_folds: folds.len(),
+            class_labels: folds.first().map(|fold| fold.class_labels.clone()).unwrap_or_default(),
+            train: ClassificationReport::from_confusion_matrix(pooled_confusion_matrix(&train)),
+            test: match test.is_empty() {
+                true => None,
+                false => Some(ClassificationReport::from_confusion_matrix(pooled_confusion_matrix(&test))),
+            },
+            train_folds: FoldStatistics::new(&train),
+            test_folds: FoldStatistics::new(&test),
+        }
+    }
+
+    /// `run_<first>-<last>_report.json` for the run ids of the folds.
+    pub fn path(save_path: &Path, first_run_id: usize, nbr_folds: usize) -> PathBuf {
+        return save_path.join(format!("run_{}-{}_report.json", first_run_id, first_run_id + nbr_folds - 1));
+    }
+
+    pub fn save(&self, path: &Path) {
+        let mut writer = BufWriter::new(File::create(path).expect("cannot create file"));
+        serde_json::to_writer_pretty(&mut writer, self).expect("cannot write");
+        writer.flush().expect("cannot write");
+    }
+}
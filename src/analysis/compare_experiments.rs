@@ -0,0 +1,227 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use crate::analysis::statistics::{holm_correction, mann_whitney_u, median, wilcoxon_signed_rank, MannWhitneyU, WilcoxonSignedRank};
+use crate::utils::run_logger::RunSummary;
+
+/// Metrics compared between the baseline and the redundancy arms.
+const METRICS: [&str; 2] = ["fitness_eval", "iterations"];
+
+fn metric_value(summary: &RunSummary, metric: &str) -> f64 {
+    return match metric {
+        "fitness_eval" => summary.fitness_eval as f64,
+        "iterations" => summary.iterations as f64,
+        _ => panic!("unknown metric: {}", metric),
+    };
+}
+
+/// Baseline vs one `redundancy_type_N/databloat_M` arm on one metric.
+/// Differences and effect sizes are arm relative to baseline: A12 > 0.5 and a positive
+/// rank-biserial correlation mean larger values in the arm.
+#[derive(Clone, Debug)]
+pub struct Comparison {
+    pub dataset: String,
+    pub nbr_nodes: usize,
+    /// `RunConfig::settings_id` of the compared runs, "-" for summaries without a stored configuration
+    pub settings: String,
+    pub redundancy_type: usize,
+    /// in percent, as in `databloat_M`
+    pub data_bloat: usize,
+    pub metric: String,
+    pub n_baseline: usize,
+    pub n_arm: usize,
+    pub median_baseline: f64,
+    pub median_arm: f64,
+    pub mann_whitney: Option<MannWhitneyU>,
+    pub mann_whitney_p_holm: Option<f64>,
+    /// runs of both groups with the same seed and fold
+    pub n_pairs: usize,
+    pub wilcoxon: Option<WilcoxonSignedRank>,
+    pub wilcoxon_p_holm: Option<f64>,
+}
+
+/// All `run_N_summary.json` files below `root`.
+pub fn collect_summaries(root: &Path) -> Vec<RunSummary> {
+    let mut summaries: Vec<RunSummary> = vec![];
+    let mut to_visit: Vec<PathBuf> = vec![root.to_path_buf()];
+
+    while let Some(dir) = to_visit.pop() {
+        let entries = fs::read_dir(&dir).unwrap_or_else(|e| panic!("cannot read {}: {}", dir.display(), e));
+        for entry in entries {
+            let path = entry.expect("cannot read directory entry").path();
+            if path.is_dir() {
+                to_visit.push(path);
+                continue;
+            }
+            let file_name = path.file_name().unwrap().to_string_lossy();
+            if file_name.starts_with("run_") && file_name.ends_with("_summary.json") {
+                summaries.push(RunSummary::load(&path));
+            }
+        }
+    }
+    return summaries;
+}
+
+/// Compares every arm with the baseline of the same dataset, number of nodes and other settings
+/// (mutation, selection, decoding, penalty, grid layout, ...), so only the redundancy differs between them.
+/// The p-values are Holm corrected per dataset, number of nodes, settings, metric and test over all arms.
+pub fn compare_to_baseline(summaries: &Vec<RunSummary>) -> Vec<Comparison> {
+    // (dataset, nbr_nodes, settings) -> baseline runs and arms (redundancy_type, data_bloat) -> runs
+    let mut groups: BTreeMap<(String, usize, String), (Vec<&RunSummary>, BTreeMap<(usize, usize), Vec<&RunSummary>>)> = BTreeMap::new();
+    for summary in summaries {
+        let settings = match &summary.config {
+            Some(config) => config.settings_id(),
+            None => "-".to_string(),
+        };
+        let group = groups
+            .entry((summary.dataset.clone(), summary.nbr_nodes, settings))
+            .or_insert_with(|| (vec![], BTreeMap::new()));
+        if summary.data_bloat == 0.0 {
+            group.0.push(summary);
+        } else {
+            let bloat = (summary.data_bloat * 100.).round() as usize;
+            group.1.entry((summary.redundancy_type, bloat)).or_default().push(summary);
+        }
+    }
+
+    let mut comparisons: Vec<Comparison> = vec![];
+    for ((dataset, nbr_nodes, settings), (baseline, arms)) in &groups {
+        if baseline.is_empty() {
+            eprintln!("no baseline runs for {} with {} nodes and settings {}, skipping", dataset, nbr_nodes, settings);
+            continue;
+        }
+        let baseline_by_run: HashMap<(u64, usize), &RunSummary> = baseline
+            .iter()
+            .map(|summary| ((summary.seed, summary.fold), *summary))
+            .collect();
+
+        for metric in METRICS {
+            let family_start = comparisons.len();
+            let baseline_values: Vec<f64> = baseline.iter().map(|summary| metric_value(summary, metric)).collect();
+
+            for ((redundancy_type, data_bloat), runs) in arms {
+                let arm_values: Vec<f64> = runs.iter().map(|summary| metric_value(summary, metric)).collect();
+
+                let pairs: Vec<(f64, f64)> = runs
+                    .iter()
+                    .filter_map(|summary| baseline_by_run
+                        .get(&(summary.seed, summary.fold))
+                        .map(|base| (metric_value(summary, metric), metric_value(base, metric))))
+                    .collect();
+                let (paired_arm, paired_baseline): (Vec<f64>, Vec<f64>) = pairs.iter().cloned().unzip();
+
+                comparisons.push(Comparison {
+                    dataset: dataset.clone(),
+                    nbr_nodes: *nbr_nodes,
+                    settings: settings.clone(),
+                    redundancy_type: *redundancy_type,
+                    data_bloat: *data_bloat,
+                    metric: metric.to_string(),
+                    n_baseline: baseline_values.len(),
+                    n_arm: arm_values.len(),
+                    median_baseline: median(&baseline_values),
+                    median_arm: median(&arm_values),
+                    mann_whitney: mann_whitney_u(&arm_values, &baseline_values),
+                    mann_whitney_p_holm: None,
+                    n_pairs: pairs.len(),
+                    wilcoxon: wilcoxon_signed_rank(&paired_arm, &paired_baseline),
+                    wilcoxon_p_holm: None,
+                });
+            }
+
+            let family = &mut comparisons[family_start..];
+            apply_holm(family, |c| c.mann_whitney.as_ref().map(|test| test.p_value), |c, p| c.mann_whitney_p_holm = Some(p));
+            apply_holm(family, |c| c.wilcoxon.as_ref().map(|test| test.p_value), |c, p| c.wilcoxon_p_holm = Some(p));
+        }
+    }
+    return comparisons;
+}
+
+fn apply_holm(family: &mut [Comparison],
+              p_value: fn(&Comparison) -> Option<f64>,
+              set_adjusted: fn(&mut Comparison, f64)) {
+    let tested: Vec<usize> = (0..family.len()).filter(|i| p_value(&family[*i]).is_some()).collect();
+    let p_values: Vec<f64> = tested.iter().map(|i| p_value(&family[*i]).unwrap()).collect();
+    for (i, adjusted) in tested.iter().zip(holm_correction(&p_values)) {
+        set_adjusted(&mut family[*i], adjusted);
+    }
+}
+
+fn optional(value: Option<f64>) -> String {
+    return match value {
+        Some(value) => format!("{:.4}", value),
+        None => "-".to_string(),
+    };
+}
+
+const HEADER: [&str; 19] = [
+    "dataset", "nbr_nodes", "settings", "redundancy_type", "data_bloat", "metric",
+    "n_baseline", "n_arm", "median_baseline", "median_arm",
+    "mwu_u", "mwu_p", "mwu_p_holm", "a12",
+    "n_pairs", "wilcoxon_w_plus", "wilcoxon_p", "wilcoxon_p_holm", "rank_biserial",
+];
+
+fn row(comparison: &Comparison) -> Vec<String> {
+    let mann_whitney = comparison.mann_whitney.as_ref();
+    let wilcoxon = comparison.wilcoxon.as_ref();
+    return vec![
+        comparison.dataset.clone(),
+        comparison.nbr_nodes.to_string(),
+        comparison.settings.clone(),
+        comparison.redundancy_type.to_string(),
+        comparison.data_bloat.to_string(),
+        comparison.metric.clone(),
+        comparison.n_baseline.to_string(),
+        comparison.n_arm.to_string(),
+        format!("{:.4}", comparison.median_baseline),
+        format!("{:.4}", comparison.median_arm),
+        optional(mann_whitney.map(|test| test.u)),
+        optional(mann_whitney.map(|test| test.p_value)),
+        optional(comparison.mann_whitney_p_holm),
+        optional(mann_whitney.map(|test| test.a12)),
+        comparison.n_pairs.to_string(),
+        optional(wilcoxon.map(|test| test.w_plus)),
+        optional(wilcoxon.map(|test| test.p_value)),
+        optional(comparison.wilcoxon_p_holm),
+        optional(wilcoxon.map(|test| test.rank_biserial)),
+    ];
+}
+
+pub fn write_csv(comparisons: &Vec<Comparison>, path: &Path) {
+    let mut writer = BufWriter::new(File::create(path).expect("cannot create file"));
+    writeln!(writer, "{}", HEADER.join(",")).expect("cannot write");
+    for comparison in comparisons {
+        writeln!(writer, "{}", row(comparison).join(",")).expect("cannot write");
+    }
+    writer.flush().expect("cannot write");
+}
+
+/// Aligned text table of the comparisons.
+pub fn format_table(comparisons: &Vec<Comparison>) -> String {
+    let rows: Vec<Vec<String>> = comparisons.iter().map(row).collect();
+    let widths: Vec<usize> = (0..HEADER.len())
+        .map(|column| rows
+            .iter()
+            .map(|row| row[column].len())
+            .chain([HEADER[column].len()])
+            .max()
+            .unwrap())
+        .collect();
+
+    let format_row = |cells: Vec<&str>| -> String {
+        cells.iter()
+            .zip(widths.iter())
+            .map(|(cell, width)| format!("{:>width$}", cell, width = width))
+            .collect::<Vec<String>>()
+            .join("  ")
+    };
+
+    let mut table = format_row(HEADER.to_vec());
+    for row in &rows {
+        table.push('\n');
+        table.push_str(&format_row(row.iter().map(|cell| cell.as_str()).collect()));
+    }
+    return table;
+}
@@ -0,0 +1,5 @@
+pub mod attribute_usage;
+pub mod usage_over_time;
+pub mod classification_report;
+pub mod statistics;
+pub mod compare_experiments;
\ No newline at end of file
@@ -0,0 +1,161 @@
+use serde::{Deserialize, Serialize};
+use statrs::distribution::{ContinuousCDF, Normal};
+
+/// Ranks starting at 1; tied values get the mean of their ranks.
+/// Also returns the tie correction term `sum(t^3 - t)` over all groups of `t` tied values.
+fn average_ranks(values: &[f64]) -> (Vec<f64>, f64) {
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|a, b| values[*a].total_cmp(&values[*b]));
+
+    let mut ranks = vec![0.; values.len()];
+    let mut tie_correction = 0.;
+    let mut start = 0;
+    while start < order.len() {
+        let mut end = start + 1;
+        while end < order.len() && values[order[end]] == values[order[start]] {
+            end += 1;
+        }
+        // positions start..end share the ranks start+1..=end
+        let rank = (start + 1 + end) as f64 / 2.;
+        for i in start..end {
+            ranks[order[i]] = rank;
+        }
+        let t = (end - start) as f64;
+        tie_correction += t * t * t - t;
+        start = end;
+    }
+    return (ranks, tie_correction);
+}
+
+/// Two-sided p-value of a standard normal test statistic.
+fn two_sided_p(z: f64) -> f64 {
+    let normal = Normal::new(0., 1.).unwrap();
+    return (2. * (1. - normal.cdf(z.abs()))).min(1.);
+}
+
+/// Distance of the statistic from its mean, reduced by the continuity correction of 0.5.
+fn continuity_corrected(statistic: f64, mean: f64) -> f64 {
+    return ((statistic - mean).abs() - 0.5).max(0.);
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MannWhitneyU {
+    /// U statistic of the first sample
+    pub u: f64,
+    /// two-sided, normal approximation with tie and continuity correction
+    pub p_value: f64,
+    /// Vargha-Delaney A12: probability that a value of the first sample is larger than one of the second
+    pub a12: f64,
+}
+
+/// Mann-Whitney U test of two independent samples. None if one of them is empty.
+pub fn mann_whitney_u(first: &[f64], second: &[f64]) -> Option<MannWhitneyU> {
+    if first.is_empty() || second.is_empty() {
+        return None;
+    }
+    let n1 = first.len() as f64;
+    let n2 = second.len() as f64;
+    let n = n1 + n2;
+
+    let values: Vec<f64> = first.iter().chain(second.iter()).cloned().collect();
+    let (ranks, tie_correction) = average_ranks(&values);
+    let rank_sum: f64 = ranks[..first.len()].iter().sum();
+
+    let u = rank_sum - n1 * (n1 + 1.) / 2.;
+    let mean = n1 * n2 / 2.;
+    let variance = n1 * n2 / 12. * ((n + 1.) - tie_correction / (n * (n - 1.)));
+
+    let p_value = match variance > 0. {
+        true => two_sided_p(continuity_corrected(u, mean) / variance.sqrt()),
+        false => 1.,
+    };
+
+    return Some(MannWhitneyU {
+        u,
+        p_value,
+        a12: u / (n1 * n2),
+    });
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WilcoxonSignedRank {
+    /// sum of the ranks of the positive differences
+    pub w_plus: f64,
+    /// number of pairs with a non-zero difference
+    pub n: usize,
+    /// two-sided, normal approximation with tie and continuity correction
+    pub p_value: f64,
+    /// matched-pairs rank-biserial correlation in [-1, 1]; positive if the first values tend to be larger
+    pub rank_biserial: f64,
+}
+
+/// Wilcoxon signed-rank test of paired samples. Pairs with equal values are dropped.
+/// None if no pair differs.
+pub fn wilcoxon_signed_rank(first: &[f64], second: &[f64]) -> Option<WilcoxonSignedRank> {
+    assert_eq!(first.len(), second.len());
+
+    let differences: Vec<f64> = first
+        .iter()
+        .zip(second.iter())
+        .map(|(a, b)| a - b)
+        .filter(|difference| *difference != 0.)
+        .collect();
+    if differences.is_empty() {
+        return None;
+    }
+    let n = differences.len() as f64;
+
+    let absolute: Vec<f64> = differences.iter().map(|difference| difference.abs()).collect();
+    let (ranks, tie_correction) = average_ranks(&absolute);
+    let w_plus: f64 = differences
+        .iter()
+        .zip(ranks.iter())
+        .filter(|(difference, _)| **difference > 0.)
+        .map(|(_, rank)| rank)
+        .sum();
+
+    let total = n * (n + 1.) / 2.;
+    let mean = total / 2.;
+    let variance = n * (n + 1.) * (2. * n + 1.) / 24. - tie_correction / 48.;
+
+    let p_value = match variance > 0. {
+        true => two_sided_p(continuity_corrected(w_plus, mean) / variance.sqrt()),
+        false => 1.,
+    };
+
+    return Some(WilcoxonSignedRank {
+        w_plus,
+        n: differences.len(),
+        p_value,
+        rank_biserial: (2. * w_plus - total) / total,
+    });
+}
+
+/// Holm-Bonferroni adjusted p-values, in the order of `p_values`.
+pub fn holm_correction(p_values: &[f64]) -> Vec<f64> {
+    let m = p_values.len();
+    let mut order: Vec<usize> = (0..m).collect();
+    order.sort_by(|a, b| p_values[*a].total_cmp(&p_values[*b]));
+
+    let mut adjusted = vec![0.; m];
+    let mut running_max: f64 = 0.;
+    for (i, index) in order.iter().enumerate() {
+        let value = ((m - i) as f64 * p_values[*index]).min(1.);
+        running_max = running_max.max(value);
+        adjusted[*index] = running_max;
+    }
+    return adjusted;
+}
+
+pub fn median(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return f64::NAN;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let middle = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        return (sorted[middle - 1] + sorted[middle]) / 2.;
+    }
+    return sorted[middle];
+}
@@ -0,0 +1,114 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::analysis::attribute_usage::{AttributeKind, AttributeLabels};
+use crate::utils::runner::InputUsageSnapshot;
+
+/// Usage of one input node over the recorded iterations.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UsageCurve {
+    pub input_id: usize,
+    pub kind: AttributeKind,
+    pub source: Option<usize>,
+    /// whether the input is active in the parent, one entry per recorded iteration
+    pub used: Vec<bool>,
+    /// recorded iterations in which the input became active
+    pub picked_up: Vec<usize>,
+    /// recorded iterations in which the input became inactive
+    pub dropped: Vec<usize>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UsageOverTime {
+    pub iterations: Vec<usize>,
+    /// one curve per input node
+    pub curves: Vec<UsageCurve>,
+    /// number of active inputs of each kind, one entry per recorded iteration
+    pub active_per_kind: BTreeMap<String, Vec<usize>>,
+}
+
+pub fn usage_over_time(history: &[InputUsageSnapshot], labels: &AttributeLabels) -> UsageOverTime {
+    let iterations: Vec<usize> = history.iter().map(|snapshot| snapshot.iteration).collect();
+
+    let mut curves: Vec<UsageCurve> = (0..labels.nbr_inputs())
+        .map(|input_id| UsageCurve {
+            input_id,
+            kind: labels.kind(input_id),
+            source: labels.source(input_id),
+            used: vec![false; history.len()],
+            picked_up: vec![],
+            dropped: vec![],
+        })
+        .collect();
+
+    for (i, snapshot) in history.iter().enumerate() {
+        for input_id in &snapshot.active_inputs {
+            curves[*input_id].used[i] = true;
+        }
+    }
+
+    for curve in &mut curves {
+        let mut previously_used = false;
+        for (i, used) in curve.used.iter().enumerate() {
+            if *used && !previously_used {
+                curve.picked_up.push(iterations[i]);
+            } else if !*used && previously_used {
+                curve.dropped.push(iterations[i]);
+            }
+            previously_used = *used;
+        }
+    }
+
+    let mut active_per_kind: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    for kind in [AttributeKind::Original, AttributeKind::Copy, AttributeKind::NoisyCopy, AttributeKind::Noise] {
+        let counts: Vec<usize> = (0..history.len())
+            .map(|i| curves
+                .iter()
+                .filter(|curve| curve.kind == kind && curve.used[i])
+                .count())
+            .collect();
+        active_per_kind.insert(kind.to_string(), counts);
+    }
+
+    return UsageOverTime {
+        iterations,
+        curves,
+        active_per_kind,
+    };
+}
+
+impl UsageOverTime {
+    /// Wide format: one row per recorded iteration, one 0/1 column per input named
+    /// `x<input_id>_<kind>_<source>`, followed by the number of active inputs per kind.
+    pub fn write_csv(&self, path: &Path) {
+        let mut writer = BufWriter::new(File::create(path).expect("cannot create file"));
+
+        let mut header: Vec<String> = vec!["iteration".to_string()];
+        for curve in &self.curves {
+            let source = match curve.source {
+                Some(source) => source.to_string(),
+                None => "none".to_string(),
+            };
+            header.push(format!("x{}_{}_{}", curve.input_id, curve.kind, source));
+        }
+        for kind in self.active_per_kind.keys() {
+            header.push(format!("active_{}", kind));
+        }
+        writeln!(writer, "{}", header.join(",")).expect("cannot write");
+
+        for (i, iteration) in self.iterations.iter().enumerate() {
+            let mut row: Vec<String> = vec![iteration.to_string()];
+            for curve in &self.curves {
+                row.push((curve.used[i] as usize).to_string());
+            }
+            for counts in self.active_per_kind.values() {
+                row.push(counts[i].to_string());
+            }
+            writeln!(writer, "{}", row.join(",")).expect("cannot write");
+        }
+
+        writer.flush().expect("cannot write");
+    }
+}
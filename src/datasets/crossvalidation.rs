@@ -1,10 +1,8 @@
 use std::iter::Iterator;
-use itertools::fold;
 
 use rand;
 use rand::prelude::SliceRandom;
-use rand::rngs::ThreadRng;
-use rand::thread_rng;
+use rand::Rng;
 
 
 pub struct CrossValidation {
@@ -14,11 +12,10 @@ pub struct CrossValidation {
 
 
 impl CrossValidation {
-    pub fn new(n_samples: usize, n_folds: usize) -> Self {
+    pub fn new<R: Rng>(n_samples: usize, n_folds: usize, rng: &mut R) -> Self {
         let mut indices: Vec<usize> = (0..n_samples).collect();
-        let mut rng = thread_rng();
 
-        indices.shuffle(&mut rng);
+        indices.shuffle(rng);
 
         let samples_per_fold = (n_samples as f32 / n_folds as f32).floor() as usize;
 
@@ -40,6 +37,11 @@ impl CrossValidation {
         }
     }
 
+    /// indices of the samples used as test data in the given fold
+    pub fn get_fold_indices(&self, fold: usize) -> &Vec<usize> {
+        return &self.fold_indices[fold];
+    }
+
     pub fn split(&mut self, mut dataset: Vec<Vec<f32>>, mut label: Vec<usize>)
                  -> (Vec<Vec<f32>>, Vec<usize>, Vec<Vec<f32>>, Vec<usize>) {
         let indices = &self.fold_indices[self.current_fold];
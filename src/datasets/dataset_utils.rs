@@ -1,10 +1,11 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use nohash_hasher::BuildNoHashHasher;
-use rand::{Rng, thread_rng};
+use rand::Rng;
 use rand::prelude::{IteratorRandom, SliceRandom};
 use crate::utils::utility_funcs::transpose;
 use statrs::distribution::Normal;
 use rand::distributions::{Distribution, Uniform};
+use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Copy)]
 pub enum RedundancyType {
@@ -13,10 +14,14 @@ pub enum RedundancyType {
     NoisyCopy,
 }
 
-pub fn add_redundancies(train_data: Vec<Vec<f32>>, test_data: Option<Vec<Vec<f32>>>, bloat: f32, r_type: RedundancyType) -> (Vec<Vec<f32>>, Option<Vec<Vec<f32>>>, HashMap<usize, Vec<usize>, BuildNoHashHasher<usize>>) {
+/// indices_from_which_values_are_copied -> List of indices into which this index is cloned into.
+/// For `RedundancyType::Noise` all noise attributes are listed under the key 0.
+pub type RedundancyMapping = HashMap<usize, Vec<usize>, BuildNoHashHasher<usize>>;
+
+pub fn add_redundancies<R: Rng>(train_data: Vec<Vec<f32>>, test_data: Option<Vec<Vec<f32>>>, bloat: f32, r_type: RedundancyType, rng: &mut R) -> (Vec<Vec<f32>>, Option<Vec<Vec<f32>>>, RedundancyMapping) {
     // mapping:
     // indices_from_which_values_are_copied -> List of indices into which this index is cloned into
-    let mut indice_insert_copy_mapping: HashMap<usize, Vec<usize>, BuildNoHashHasher<usize>> = HashMap::default();
+    let mut indice_insert_copy_mapping: RedundancyMapping = HashMap::default();
 
     if bloat <= 0.0 {
         return (train_data, test_data, indice_insert_copy_mapping);
@@ -34,7 +39,6 @@ pub fn add_redundancies(train_data: Vec<Vec<f32>>, test_data: Option<Vec<Vec<f32
     }
 
     let nbr_additionals: usize = ((train_data.len() as f32) * bloat).ceil() as usize;
-    let mut rng = thread_rng();
 
     match r_type {
         RedundancyType::Copy => {
@@ -48,7 +52,7 @@ pub fn add_redundancies(train_data: Vec<Vec<f32>>, test_data: Option<Vec<Vec<f32
             let choices: Vec<usize> = (0..train_data.len()).collect();
             for _ in 0..nbr_additionals {
                 // choose index from og dataset
-                let index = *choices.choose(&mut rng).unwrap();
+                let index = *choices.choose(rng).unwrap();
                 indices_from_og_dataset.push(index);
 
                 indice_insert_copy_mapping.insert(index, vec![]);
@@ -56,9 +60,8 @@ pub fn add_redundancies(train_data: Vec<Vec<f32>>, test_data: Option<Vec<Vec<f32
 
             // insert redundancies
             for redundancy_index in indices_from_og_dataset {
-                let input_index = (0..train_data.len()).choose(&mut rng).unwrap();
+                let input_index = (0..train_data.len()).choose(rng).unwrap();
 
-                indice_insert_copy_mapping.get_mut(&redundancy_index).unwrap().push(input_index);
                 // indices must be updated. if an input_index is smaller than an already input index,
                 // bigger indices get moved by one, as the attribute is also moved to the right
                 indice_insert_copy_mapping = indice_insert_copy_mapping.iter_mut().map(|(key, val)| {
@@ -69,6 +72,7 @@ pub fn add_redundancies(train_data: Vec<Vec<f32>>, test_data: Option<Vec<Vec<f32
                     }
                     (*key, val.clone())
                 }).collect();
+                indice_insert_copy_mapping.get_mut(&redundancy_index).unwrap().push(input_index);
 
                 train_data.insert(input_index, og_train_data[redundancy_index].clone());
                 if test_data.is_some() {
@@ -86,7 +90,7 @@ pub fn add_redundancies(train_data: Vec<Vec<f32>>, test_data: Option<Vec<Vec<f32
             let mut redundancy_indices: Vec<usize> = Vec::with_capacity(nbr_additionals);
             let choices: Vec<usize> = (0..train_data.len()).collect();
             for _ in 0..nbr_additionals {
-                let index = *choices.choose(&mut rng).unwrap();
+                let index = *choices.choose(rng).unwrap();
                 redundancy_indices.push(index);
                 indice_insert_copy_mapping.insert(index, vec![]);
             }
@@ -97,7 +101,7 @@ pub fn add_redundancies(train_data: Vec<Vec<f32>>, test_data: Option<Vec<Vec<f32
 
             // insert redundancies
             for redundancy_index in redundancy_indices {
-                let input_index = (0..train_data.len()).choose(&mut rng).unwrap();
+                let input_index = (0..train_data.len()).choose(rng).unwrap();
 
                 // indices must be updated. if an input_index is smaller than an already input index,
                 // bigger indices get moved by one, as the attribute is also moved to the right
@@ -114,7 +118,7 @@ pub fn add_redundancies(train_data: Vec<Vec<f32>>, test_data: Option<Vec<Vec<f32
 
                 // noise data
                 let train_data_to_insert = og_train_data[redundancy_index].clone();
-                let random_noise_ranges: Vec<f32> = (0..train_data_to_insert.len()).map(|_| between.sample(&mut rng)).collect();
+                let random_noise_ranges: Vec<f32> = (0..train_data_to_insert.len()).map(|_| between.sample(rng)).collect();
                 let train_data_to_insert: Vec<f32> = train_data_to_insert
                     .iter()
                     .zip(random_noise_ranges.iter())
@@ -144,7 +148,7 @@ pub fn add_redundancies(train_data: Vec<Vec<f32>>, test_data: Option<Vec<Vec<f32
 
             // add noise:
             for _ in 0..nbr_additionals {
-                let input_index = (0..train_data.len()).choose(&mut rng).unwrap();
+                let input_index = (0..train_data.len()).choose(rng).unwrap();
 
                 // indices must be updated. if an input_index is smaller than an already input index,
                 // bigger indices get moved by one, as the attribute is also moved to the right
@@ -185,6 +189,12 @@ pub fn add_redundancies(train_data: Vec<Vec<f32>>, test_data: Option<Vec<Vec<f32
 }
 
 
+/// Input node ids of all attributes added by `add_redundancies`.
+pub fn redundant_input_ids(mapping: &RedundancyMapping) -> HashSet<usize, BuildNoHashHasher<usize>> {
+    return mapping.values().flatten().cloned().collect();
+}
+
+
 fn mean(v: &Vec<f32>) -> f32 {
     let len = v.len() as f32;
     return v.iter().sum::<f32>() / len;
@@ -201,32 +211,132 @@ fn standard_deviation(v: &Vec<f32>) -> f32 {
     return var.sqrt();
 }
 
-pub fn standardize_dataset(data: Vec<Vec<f32>>) -> Vec<Vec<f32>> {
-    let train_data_t = transpose(data);
-    let mut standardized_data: Vec<Vec<f32>> = Vec::with_capacity(train_data_t.len());
+/// Per-column standardization statistics, so new data can be transformed like the training data.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Scaler {
+    pub means: Vec<f32>,
+    pub stds: Vec<f32>,
+}
 
-    for entry_t in train_data_t {
-        let mean = mean(&entry_t);
-        let std = standard_deviation(&entry_t);
-        let std_ized = entry_t
+impl Scaler {
+    pub fn fit(data: &Vec<Vec<f32>>) -> Self {
+        let data_t = transpose(data.clone());
+        Self {
+            means: data_t.iter().map(mean).collect(),
+            stds: data_t.iter().map(standard_deviation).collect(),
+        }
+    }
+
+    pub fn transform(&self, data: Vec<Vec<f32>>) -> Vec<Vec<f32>> {
+        return data
             .iter()
-            .map(|elem| (elem - mean) / std)
-            .collect::<Vec<f32>>();
-        standardized_data.push(std_ized);
+            .map(|row| {
+                assert_eq!(row.len(), self.means.len(), "wrong number of attributes");
+                row.iter()
+                    .zip(self.means.iter().zip(self.stds.iter()))
+                    .map(|(elem, (mean, std))| (elem - mean) / std)
+                    .collect()
+            })
+            .collect();
     }
+}
+
+/// Maps the class labels of a dataset to `0..k`. If all labels are numeric, they are ordered by value
+/// (`"1.0"` and `"1"` are the same class), otherwise all labels are compared as text and ordered alphabetically.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LabelEncoder {
+    /// original label of every class
+    pub classes: Vec<String>,
+    /// all labels were numeric when fitted, labels are then compared by value
+    pub numeric: bool,
+}
+
+impl LabelEncoder {
+    pub fn fit(labels: &[&str]) -> Self {
+        let values: Option<Vec<f64>> = labels.iter().map(|label| label.trim().parse::<f64>().ok()).collect();
+        let numeric = values.is_some();
 
-    let standardized_data = transpose(standardized_data);
-    return standardized_data;
+        let classes: Vec<String> = match values {
+            Some(mut values) => {
+                values.sort_by(|a, b| a.total_cmp(b));
+                values.dedup();
+                values.iter().map(|value| value.to_string()).collect()
+            }
+            None => {
+                let mut classes: Vec<String> = labels.iter().map(|label| label.trim().to_string()).collect();
+                classes.sort();
+                classes.dedup();
+                classes
+            }
+        };
+        assert!(!classes.is_empty(), "no labels");
+
+        Self {
+            classes,
+            numeric,
+        }
+    }
+
+    /// Fits on the training labels. Panics if a test label does not occur in the training set.
+    pub fn fit_train_test(train_labels: &[&str], test_labels: &[&str]) -> Self {
+        let encoder = LabelEncoder::fit(train_labels);
+        if let Some(unknown) = test_labels.iter().find(|label| encoder.encode(label).is_none()) {
+            panic!("test label {} does not occur in the training labels {:?}", unknown, encoder.classes);
+        }
+        return encoder;
+    }
+
+    pub fn nbr_classes(&self) -> usize {
+        return self.classes.len();
+    }
+
+    pub fn encode(&self, label: &str) -> Option<usize> {
+        let label = label.trim();
+        let label = match (self.numeric, label.parse::<f64>()) {
+            (true, Ok(value)) => value.to_string(),
+            _ => label.to_string(),
+        };
+        return self.classes.iter().position(|class| *class == label);
+    }
+
+    pub fn transform(&self, labels: &[&str]) -> Vec<usize> {
+        return labels
+            .iter()
+            .map(|label| self.encode(label)
+                .unwrap_or_else(|| panic!("unknown label {}, expected one of {:?}", label, self.classes)))
+            .collect();
+    }
+
+    pub fn decode(&self, class: usize) -> &str {
+        return &self.classes[class];
+    }
+}
+
+/// Classes of `test_labels` that do not occur in `train_labels`, sorted.
+pub fn unseen_labels(train_labels: &Vec<usize>, test_labels: &Vec<usize>) -> Vec<usize> {
+    let mut unseen: Vec<usize> = test_labels
+        .iter()
+        .filter(|label| !train_labels.contains(label))
+        .cloned()
+        .collect();
+    unseen.sort();
+    unseen.dedup();
+    return unseen;
+}
+
+pub fn standardize_dataset(data: Vec<Vec<f32>>) -> Vec<Vec<f32>> {
+    let scaler = Scaler::fit(&data);
+    return scaler.transform(data);
 }
 
 /// Inefficient but does the trick. Shuffle data and corresponding label.
-pub fn shuffle(v1: Vec<Vec<f32>>, v2: Vec<usize>) -> (Vec<Vec<f32>>, Vec<usize>) {
+pub fn shuffle<R: Rng>(v1: Vec<Vec<f32>>, v2: Vec<usize>, rng: &mut R) -> (Vec<Vec<f32>>, Vec<usize>) {
     assert!(v1.len() == v2.len());
 
     let len = v1.len();
     let mut len_range: Vec<usize> = (0..len).collect();
 
-    len_range.shuffle(&mut thread_rng());
+    len_range.shuffle(rng);
 
     let mut new1: Vec<Vec<f32>> = vec![];
     let mut new2: Vec<usize> = vec![];
@@ -239,7 +349,7 @@ pub fn shuffle(v1: Vec<Vec<f32>>, v2: Vec<usize>) -> (Vec<Vec<f32>>, Vec<usize>)
 }
 
 
-pub fn preprocess_and_split(datas: Vec<Vec<f32>>, labels: Vec<usize>, standardize: bool)
+pub fn preprocess_and_split<R: Rng>(datas: Vec<Vec<f32>>, labels: Vec<usize>, standardize: bool, rng: &mut R)
                             -> (Vec<Vec<f32>>, Vec<usize>, Vec<Vec<f32>>, Vec<usize>)
 {
     let datas = match standardize {
@@ -247,7 +357,7 @@ pub fn preprocess_and_split(datas: Vec<Vec<f32>>, labels: Vec<usize>, standardiz
         false => { datas }
     };
 
-    let (datas, labels) = shuffle(datas, labels);
+    let (datas, labels) = shuffle(datas, labels, rng);
     let total_len = datas.len();
     let split_idx = total_len as f32 * 0.8;
     let split_idx = split_idx as usize;
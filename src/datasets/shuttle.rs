@@ -1,13 +1,15 @@
 use std::fs;
 use std::path::Path;
-use crate::datasets::dataset_utils::standardize_dataset;
+use crate::datasets::dataset_utils::{LabelEncoder, Scaler};
 use crate::datasets::fitness_metric_type::FitnessFuncType;
 
 pub fn get_dataset(dataset_path: String) -> (Vec<Vec<f32>>,
                          Vec<usize>,
                          Vec<Vec<f32>>,
                          Vec<usize>,
-                         FitnessFuncType) {
+                         FitnessFuncType,
+                         Scaler,
+                         LabelEncoder) {
     let contents_data_train = fs::read_to_string(Path::new(&dataset_path).join("attributes_train.csv"))
         .expect("Should have been able to read the file");
     let contents_label_train = fs::read_to_string(Path::new(&dataset_path).join("label_train.csv"))
@@ -34,18 +36,16 @@ pub fn get_dataset(dataset_path: String) -> (Vec<Vec<f32>>,
         let converted_data: Vec<f32> = line.iter().map(|val| val.parse::<f32>().unwrap()).collect();
         train_data.push(converted_data);
     }
-    for line in contents_label_train {
-        let lbl = line.parse::<f32>().unwrap() as usize;
-        train_label.push(lbl);
-    }
-    for line in contents_label_test {
-        let lbl = line.parse::<f32>().unwrap() as usize;
-        train_label.push(lbl);
-    }
+    let raw_labels_train: Vec<&str> = contents_label_train.collect();
+    let raw_labels_test: Vec<&str> = contents_label_test.collect();
+    let label_encoder = LabelEncoder::fit_train_test(&raw_labels_train, &raw_labels_test);
+    train_label.extend(label_encoder.transform(&raw_labels_train));
+    train_label.extend(label_encoder.transform(&raw_labels_test));
 
-    let train_data = standardize_dataset(train_data);
+    let scaler = Scaler::fit(&train_data);
+    let train_data = scaler.transform(train_data);
 
 
-    return (train_data, train_label, vec![], vec![], FitnessFuncType::ClassificationMultiClass);
+    return (train_data, train_label, vec![], vec![], FitnessFuncType::ClassificationMultiClass, scaler, label_encoder);
 
 }
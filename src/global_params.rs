@@ -1,27 +1,64 @@
 use std::fmt::{Display, Formatter};
+use serde::{Deserialize, Serialize};
 use crate::datasets::fitness_metric_type::FitnessFuncType;
+use crate::utils::class_costs::ClassCosts;
+use crate::utils::connection_sampling::ConnectionSampling;
+use crate::utils::multiclass_decoding::MulticlassDecoding;
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct CgpParameters {
+    /// number of computational nodes, `rows * columns`
     pub graph_width: usize,
+    /// rows of the grid of computational nodes; the columns follow from `graph_width`
+    #[serde(default = "default_rows")]
+    pub rows: usize,
+    /// how many preceding columns a computational node may connect to; None allows all of them
+    #[serde(default)]
+    pub levels_back: Option<usize>,
     pub mu: usize,
     pub lambda: usize,
     pub eval_after_iterations: usize,
     pub nbr_inputs: usize,
     pub nbr_outputs: usize,
+    /// classes of the label encoder, also those missing in a training fold; 0 for regression
+    #[serde(default)]
+    pub nbr_classes: usize,
     pub fitness_func_type: FitnessFuncType,
+    #[serde(default)]
+    pub connection_sampling: ConnectionSampling,
+    /// only used for `FitnessFuncType::ClassificationMultiClass`
+    #[serde(default)]
+    pub multiclass_decoding: MulticlassDecoding,
+    /// cost-sensitive fitness for classification
+    #[serde(default)]
+    pub class_costs: Option<ClassCosts>,
 }
 
+pub fn default_rows() -> usize {
+    return 1;
+}
+
+impl CgpParameters {
+    pub fn columns(&self) -> usize {
+        return self.graph_width / self.rows;
+    }
+}
 
 impl Display for CgpParameters {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "############ Parameters ############\n")?;
         write!(f, "graph_width: {}\n", self.graph_width)?;
+        write!(f, "rows: {}\n", self.rows)?;
+        write!(f, "levels_back: {:?}\n", self.levels_back)?;
         write!(f, "mu: {}\n", self.mu)?;
         write!(f, "lambda: {}\n", self.lambda)?;
         write!(f, "eval_after_iterations: {}\n", self.eval_after_iterations)?;
         write!(f, "nbr_inputs: {}\n", self.nbr_inputs)?;
         write!(f, "nbr_outputs: {}\n", self.nbr_outputs)?;
+        write!(f, "nbr_classes: {}\n", self.nbr_classes)?;
+        write!(f, "connection_sampling: {:?}\n", self.connection_sampling)?;
+        write!(f, "multiclass_decoding: {:?}\n", self.multiclass_decoding)?;
+        write!(f, "class_costs: {:?}\n", self.class_costs)?;
         write!(f, "#########################\n")
     }
 }
\ No newline at end of file
@@ -2,4 +2,5 @@ pub mod utils;
 pub mod global_params;
 pub mod datasets;
 pub mod vanilla_cgp;
+pub mod analysis;
 
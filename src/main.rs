@@ -1,21 +1,41 @@
-use std::io::{BufWriter, Write};
 use cgp::global_params::CgpParameters;
 use cgp::datasets::*;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use std::fs;
-use std::fs::File;
-use std::path::{Path, PathBuf};
-use std::process::exit;
-use itertools::Itertools;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rayon::prelude::*;
 use cgp::datasets::crossvalidation::CrossValidation;
-use cgp::datasets::dataset_utils::{add_redundancies, RedundancyType};
+use cgp::datasets::dataset_utils::{add_redundancies, redundant_input_ids, unseen_labels, RedundancyType};
 use cgp::datasets::fitness_metric_type::FitnessFuncType;
+use cgp::analysis::attribute_usage::{attribute_usage, AttributeLabels};
+use cgp::analysis::usage_over_time::usage_over_time;
+use cgp::analysis::compare_experiments::{collect_summaries, compare_to_baseline, format_table, write_csv};
+use cgp::analysis::classification_report::{ClassificationReport, CrossValidationReport, FoldReport};
+use cgp::utils::checkpoint::Checkpoint;
+use cgp::utils::logging_stuff::LoggingStuff;
+use cgp::utils::class_costs::ClassCosts;
+use cgp::utils::connection_sampling::ConnectionSampling;
+use cgp::utils::multiclass_decoding::MulticlassDecoding;
+use cgp::utils::input_penalty::{InputPenalty, InputPenaltyTerm};
+use cgp::utils::mutation_strategy::{MutationStrategy, RateControl};
+use cgp::utils::model::{read_samples, Model};
+use cgp::utils::experiment::{ExperimentGrid, RunConfig, NBR_FOLDS};
+use cgp::utils::run_logger::{IterationRecord, LogFormat, ParetoFront, ParetoSolution, RunLogger, RunSummary};
+use cgp::utils::selection::Selection;
 use cgp::utils::runner::Runner;
+use cgp::utils::stopping_criteria::StoppingCriteria;
 
 
 #[derive(Parser, Clone)]
 #[clap(author, version, about, name = "testname")]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     #[arg(long, default_value_t = 0)]
     run_id: usize,
 
@@ -31,28 +51,409 @@ struct Args {
     #[arg(long, default_value_t = 2)]
     redundancy_type: usize,
 
+    #[arg(long, required = true)]
+    dataset_path: Option<String>,
+
+    /// seed for all random decisions; drawn randomly if not given
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// number of worker threads for folds and offspring evaluation; 0 uses all cores
+    #[arg(long, default_value_t = 1)]
+    threads: usize,
+
+    /// stop after this many iterations; 0 disables the limit
+    #[arg(long, default_value_t = 100_000)]
+    max_iterations: usize,
+
+    /// stop after this many fitness evaluations
+    #[arg(long)]
+    max_fitness_evaluations: Option<usize>,
+
+    /// wall-clock budget per fold in seconds
+    #[arg(long)]
+    max_seconds: Option<f64>,
+
+    /// stop once the best training fitness is at most this value, e.g. 0.01
+    #[arg(long)]
+    target_fitness: Option<f32>,
+
+    /// stop if the best fitness did not improve for this many iterations
+    #[arg(long)]
+    stagnation: Option<usize>,
+
+    /// write a checkpoint every n iterations; 0 disables checkpoints
+    #[arg(long, default_value_t = 10_000)]
+    checkpoint_interval: usize,
+
+    /// continue from the checkpoints in the output directory and skip completed folds
+    #[arg(long)]
+    resume: bool,
+
+    /// log the genome of the parent every n iterations to run_N_genomes.txt.gz; 0 disables the log
+    #[arg(long, default_value_t = 0)]
+    log_genomes: usize,
+
+    /// format of the run log: jsonl or csv
+    #[arg(long, default_value = "jsonl")]
+    log_format: LogFormat,
+
+    /// write a record to the run log every n iterations; 0 only logs the final iteration
+    #[arg(long, default_value_t = 500)]
+    eval_after_iterations: usize,
+
+    /// record the active inputs of the parent every n iterations to run_N_input_usage.csv; 0 disables it
+    #[arg(long, default_value_t = 0)]
+    track_input_usage: usize,
+
+    /// how offspring are created: single_active, point or genewise
+    #[arg(long, default_value = "single_active", value_parser = ["single_active", "point", "genewise"])]
+    mutation: String,
+
+    /// probability of every gene to be mutated, for point mutation
+    #[arg(long, default_value_t = 0.05)]
+    mutation_rate: f32,
+
+    /// probability of every connection gene to be mutated, for genewise mutation
+    #[arg(long, default_value_t = 0.05)]
+    connection_mutation_rate: f32,
+
+    /// probability of every function gene to be mutated, for genewise mutation
+    #[arg(long, default_value_t = 0.05)]
+    function_mutation_rate: f32,
+
+    /// for point and genewise mutation: mutate an active gene if none was hit
+    #[arg(long)]
+    force_active_mutation: bool,
+
+    /// how the rate of point and genewise mutation changes: fixed, self_adaptive, one_fifth, linear or exponential
+    #[arg(long, default_value = "fixed", value_parser = ["fixed", "self_adaptive", "one_fifth", "linear", "exponential"])]
+    rate_control: String,
+
+    /// learning rate of self-adaptive mutation rates
+    #[arg(long, default_value_t = 0.2)]
+    rate_tau: f32,
+
+    /// iterations between two adaptations of the 1/5th success rule
+    #[arg(long, default_value_t = 10)]
+    rate_window: usize,
+
+    /// factor by which the 1/5th success rule changes the mutation rate
+    #[arg(long, default_value_t = 1.5)]
+    rate_factor: f32,
+
+    /// lower bound of self-adaptive and 1/5th rule mutation rates
+    #[arg(long, default_value_t = 0.001)]
+    min_mutation_rate: f32,
+
+    /// upper bound of self-adaptive and 1/5th rule mutation rates
+    #[arg(long, default_value_t = 0.5)]
+    max_mutation_rate: f32,
+
+    /// mutation rate at the end of a linear or exponential decay
+    #[arg(long, default_value_t = 0.001)]
+    final_mutation_rate: f32,
+
+    /// iterations of a linear or exponential decay; 0 uses max-iterations
+    #[arg(long, default_value_t = 0)]
+    decay_iterations: usize,
+
+    /// distribution of new connections: uniform, recent_nodes or input_split
+    #[arg(long, default_value = "uniform", value_parser = ["uniform", "recent_nodes", "input_split"])]
+    connection_sampling: String,
+
+    /// for recent_nodes sampling: number of computational nodes directly before a node it may connect to,
+    /// besides the inputs; not to be confused with levels-back, which counts columns of the grid
+    #[arg(long, default_value_t = 10)]
+    connection_window: usize,
+
+    /// for input_split sampling: probability that a new connection points to an input node
+    #[arg(long, default_value_t = 0.5)]
+    input_connection_probability: f32,
+
+    /// rows of the grid of computational nodes; nbr-nodes must be a multiple of it
+    #[arg(long, default_value_t = 1)]
+    rows: usize,
+
+    /// number of preceding columns a computational node may connect to; all columns if not given
+    #[arg(long)]
+    levels_back: Option<usize>,
+
+    /// decoding of multiclass outputs: argmax, thresholds, one_vs_rest or softmax
+    #[arg(long, default_value = "argmax", value_parser = ["argmax", "thresholds", "one_vs_rest", "softmax"])]
+    multiclass_decoding: String,
+
+    /// TOML or JSON file with misclassification costs or class weights for the fitness
     #[arg(long)]
-    dataset_path: String,
+    class_costs: Option<PathBuf>,
+
+    /// selection of the parents: fitness, parsimony (fewer active nodes on ties)
+    /// or nsga2 (error, active nodes and used inputs)
+    #[arg(long, default_value = "fitness", value_parser = ["fitness", "parsimony", "nsga2"])]
+    selection: String,
+
+    /// for nsga2 selection: number of parents and offspring per iteration
+    #[arg(long, default_value_t = 50)]
+    nsga2_population: usize,
+
+    /// penalty added to the training fitness per distinct input used; 0 disables it
+    #[arg(long, default_value_t = 0.)]
+    input_penalty: f32,
+
+    /// count an attribute and its copies as one input for the input penalty
+    #[arg(long, default_value_t = false)]
+    penalty_count_copies_once: bool,
+}
+
+#[derive(Subcommand, Clone)]
+enum Command {
+    /// Runs every combination of a parameter grid, skipping already completed runs
+    Experiment {
+        /// TOML or JSON file describing the grid
+        #[arg(long)]
+        grid: PathBuf,
+
+        /// number of worker threads; 0 uses all cores
+        #[arg(long, default_value_t = 0)]
+        threads: usize,
+    },
+    /// Classifies the samples of a CSV file with a saved model
+    Predict {
+        /// `run_N_model.json` written by a run
+        #[arg(long)]
+        model: PathBuf,
+
+        /// comma separated raw attribute values, one sample per line, in the column order of the dataset
+        #[arg(long)]
+        data: PathBuf,
+
+        /// file for the predicted classes, one per line; printed if not given
+        #[arg(long)]
+        output: Option<PathBuf>,
+
+        /// seed for the noise of noisy copies and noise attributes; the model does not keep the noise of the run,
+        /// it is drawn anew, so predictions that use noisy inputs may differ from those of the run
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+    },
+    /// Compares the redundancy arms with the baseline using Mann-Whitney U and Wilcoxon signed-rank tests
+    Analyze {
+        /// directory with the run summaries
+        #[arg(long, default_value = "Experiments_Output")]
+        input: PathBuf,
+
+        /// CSV file for the table; defaults to `analysis.csv` in the input directory
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
 }
 
 fn main() {
     // ################################################################################
     // ############################ Arguments #########################################
     // ################################################################################
-    let mut args = Args::parse();
+    let args = Args::parse();
+
+    match args.command {
+        Some(Command::Experiment { grid, threads }) => {
+            build_thread_pool(threads);
+            run_grid(ExperimentGrid::load(&grid));
+        }
+        Some(Command::Predict { model, data, output, seed }) => {
+            predict(&model, &data, output, seed);
+        }
+        Some(Command::Analyze { input, output }) => {
+            let summaries = collect_summaries(&input);
+            let comparisons = compare_to_baseline(&summaries);
+            println!("{}", format_table(&comparisons));
+            write_csv(&comparisons, &output.unwrap_or(input.join("analysis.csv")));
+        }
+        None => {
+            build_thread_pool(args.threads);
+
+            let decay_iterations = match args.decay_iterations {
+                0 => args.max_iterations,
+                iterations => iterations,
+            };
+            let mut config = RunConfig {
+                run_id: args.run_id,
+                dataset: args.dataset,
+                dataset_path: args.dataset_path.unwrap(),
+                nbr_nodes: args.nbr_nodes,
+                rows: args.rows,
+                levels_back: args.levels_back,
+                data_bloat: args.data_bloat,
+                redundancy_type: args.redundancy_type,
+                seed: 0,
+                stopping_criteria: StoppingCriteria {
+                    max_iterations: (args.max_iterations > 0).then_some(args.max_iterations),
+                    max_fitness_evaluations: args.max_fitness_evaluations,
+                    max_seconds: args.max_seconds,
+                    target_fitness: args.target_fitness,
+                    stagnation: args.stagnation,
+                },
+                checkpoint_interval: args.checkpoint_interval,
+                resume: args.resume,
+                log_genomes: args.log_genomes,
+                log_format: args.log_format,
+                eval_after_iterations: args.eval_after_iterations,
+                track_input_usage: args.track_input_usage,
+                mutation: match args.mutation.as_str() {
+                    "point" => MutationStrategy::Point {
+                        rate: args.mutation_rate,
+                        force_active: args.force_active_mutation,
+                    },
+                    "genewise" => MutationStrategy::Genewise {
+                        connection_rate: args.connection_mutation_rate,
+                        function_rate: args.function_mutation_rate,
+                        force_active: args.force_active_mutation,
+                    },
+                    _ => MutationStrategy::SingleActive,
+                },
+                rate_control: match args.rate_control.as_str() {
+                    "self_adaptive" => RateControl::SelfAdaptive {
+                        tau: args.rate_tau,
+                        min_rate: args.min_mutation_rate,
+                        max_rate: args.max_mutation_rate,
+                    },
+                    "one_fifth" => RateControl::OneFifth {
+                        window: args.rate_window,
+                        factor: args.rate_factor,
+                        min_rate: args.min_mutation_rate,
+                        max_rate: args.max_mutation_rate,
+                    },
+                    "linear" => RateControl::Linear {
+                        final_rate: args.final_mutation_rate,
+                        iterations: decay_iterations,
+                    },
+                    "exponential" => RateControl::Exponential {
+                        final_rate: args.final_mutation_rate,
+                        iterations: decay_iterations,
+                    },
+                    _ => RateControl::Fixed,
+                },
+                connection_sampling: match args.connection_sampling.as_str() {
+                    "recent_nodes" => ConnectionSampling::RecentNodes {
+                        window: args.connection_window,
+                    },
+                    "input_split" => ConnectionSampling::InputSplit {
+                        input_probability: args.input_connection_probability,
+                    },
+                    _ => ConnectionSampling::Uniform,
+                },
+                multiclass_decoding: match args.multiclass_decoding.as_str() {
+                    "thresholds" => MulticlassDecoding::Thresholds,
+                    "one_vs_rest" => MulticlassDecoding::OneVsRest,
+                    "softmax" => MulticlassDecoding::Softmax,
+                    _ => MulticlassDecoding::Argmax,
+                },
+                class_costs: args.class_costs,
+                selection: match args.selection.as_str() {
+                    "parsimony" => Selection::Parsimony,
+                    "nsga2" => Selection::Nsga2 {
+                        population_size: args.nsga2_population,
+                    },
+                    _ => Selection::Fitness,
+                },
+                input_penalty: InputPenalty {
+                    weight: args.input_penalty,
+                    count_copies_once: args.penalty_count_copies_once,
+                },
+            };
+            config.validate();
+            config.seed = match args.seed {
+                Some(seed) => seed,
+                None => resumed_seed(&config).unwrap_or_else(|| rand::thread_rng().gen()),
+            };
+            run(&config);
+        }
+    }
+}
+
+/// Seed of an existing checkpoint or summary, so `--resume` works without passing the seed again.
+fn resumed_seed(config: &RunConfig) -> Option<u64> {
+    if !config.resume {
+        return None;
+    }
+    let save_path = config.save_path();
+    return (0..NBR_FOLDS)
+        .filter_map(|fold| {
+            let run_id = config.run_id + fold;
+            if let Some(checkpoint) = Checkpoint::load(&Checkpoint::path(&save_path, run_id)) {
+                return Some(checkpoint.seed);
+            }
+            let summary_path = RunSummary::path(&save_path, run_id);
+            return summary_path.exists().then(|| RunSummary::load(&summary_path).seed);
+        })
+        .next();
+}
+
+fn build_thread_pool(threads: usize) {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build_global()
+        .expect("cannot build thread pool");
+}
+
+fn run_grid(grid: ExperimentGrid) {
+    grid.validate();
+    let runs: Vec<RunConfig> = grid.runs();
+    let open_runs: Vec<RunConfig> = runs
+        .iter()
+        .filter(|config| !config.is_completed())
+        .cloned()
+        .collect();
+
+    println!("{} of {} runs already completed", runs.len() - open_runs.len(), runs.len());
+
+    // runs and their folds share the same pool, so idle cores pick up folds of other runs
+    open_runs.par_iter().for_each(run);
+}
+
+fn predict(model_path: &PathBuf, data_path: &PathBuf, output: Option<PathBuf>, seed: u64) {
+    let model = Model::load(model_path);
+    let samples = read_samples(data_path);
+
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let predictions = model.predict(samples, &mut rng);
+
+    // original class labels; class ids for models saved without them
+    let lines: Vec<String> = predictions
+        .iter()
+        .map(|prediction| match &model.label_encoder {
+            Some(label_encoder) => label_encoder.decode(*prediction).to_string(),
+            None => prediction.to_string(),
+        })
+        .collect();
+    match output {
+        Some(path) => fs::write(path, lines.join("\n") + "\n").expect("cannot write predictions"),
+        None => println!("{}", lines.join("\n")),
+    }
+}
+
+fn run(args: &RunConfig) {
+    // the shuffle, the folds and the fold seeds only depend on the seed, so the baseline and every
+    // redundancy arm train and test on the same samples for the same seed and fold
+    let mut rng = ChaCha8Rng::seed_from_u64(args.seed);
+    // the redundant attributes are drawn from a stream of their own
+    let mut redundancy_rng = ChaCha8Rng::seed_from_u64(args.seed);
+    redundancy_rng.set_stream(1);
+
     let (
         data,
         label,
         _,
         _,
-        fitness_type
+        fitness_type,
+        scaler,
+        label_encoder
     ) = match args.dataset {
-        0 => abalone::get_dataset(args.dataset_path),  // ~3h
-        1 => credit::get_dataset(args.dataset_path),  // ~10 min?
-        2 => shuttle::get_dataset(args.dataset_path),  // ~6h
-        3 => breast_cancer::get_dataset(args.dataset_path),  // ~10 min
-        4 => page_blocks::get_dataset(args.dataset_path),  // ~1h
-        5 => waveform::get_dataset(args.dataset_path),  // ~10min
+        0 => abalone::get_dataset(args.dataset_path.clone(), &mut rng),  // ~3h
+        1 => credit::get_dataset(args.dataset_path.clone()),  // ~10 min?
+        2 => shuttle::get_dataset(args.dataset_path.clone()),  // ~6h
+        3 => breast_cancer::get_dataset(args.dataset_path.clone(), &mut rng),  // ~10 min
+        4 => page_blocks::get_dataset(args.dataset_path.clone(), &mut rng),  // ~1h
+        5 => waveform::get_dataset(args.dataset_path.clone(), &mut rng),  // ~10min
         _ => panic!("Wrong dataset"),
     };
 
@@ -66,140 +467,323 @@ fn main() {
     let (data, _, indice_insert_copy_mapping) = add_redundancies(data,
                                                                  None,
                                                                  args.data_bloat,
-                                                                 redundancy_type);
+                                                                 redundancy_type,
+                                                                 &mut redundancy_rng);
 
     let nbr_outputs: usize = match fitness_type {
         FitnessFuncType::Regression => { 1 }
         FitnessFuncType::ClassificationBinary => { 1 }
-        FitnessFuncType::ClassificationMultiClass => { *label.iter().max().unwrap() }
+        FitnessFuncType::ClassificationMultiClass => { args.multiclass_decoding.nbr_outputs(label_encoder.nbr_classes()) }
+    };
+    if fitness_type == FitnessFuncType::ClassificationBinary {
+        assert_eq!(label_encoder.nbr_classes(), 2, "binary classification needs two classes, got {:?}", label_encoder.classes);
+    }
+
+    assert!(args.rows > 0 && args.nbr_nodes.is_multiple_of(args.rows),
+            "nbr_nodes ({}) must be a multiple of rows ({})", args.nbr_nodes, args.rows);
+    assert_ne!(args.levels_back, Some(0), "levels_back must be at least 1");
+
+    let class_costs: Option<ClassCosts> = args.class_costs
+        .as_ref()
+        .map(|path| ClassCosts::load(path, &label_encoder));
+    if class_costs.is_some() {
+        assert!(fitness_type != FitnessFuncType::Regression, "class costs need a classification dataset");
+        assert!(fitness_type == FitnessFuncType::ClassificationBinary
+                    || matches!(args.multiclass_decoding, MulticlassDecoding::Argmax | MulticlassDecoding::Thresholds),
+                "class costs need argmax or thresholds decoding, got {:?}", args.multiclass_decoding);
+    }
+
+    // classes of the classification report; no report for regression
+    let nbr_classes: Option<usize> = match fitness_type {
+        FitnessFuncType::Regression => None,
+        _ => Some(label_encoder.nbr_classes()),
     };
 
     let params = CgpParameters {
         graph_width: args.nbr_nodes,
+        rows: args.rows,
+        levels_back: args.levels_back,
         mu: 1,
         lambda: 4,
-        eval_after_iterations: 500,
+        eval_after_iterations: args.eval_after_iterations,
         nbr_inputs: data[0].len(),
         nbr_outputs,
+        nbr_classes: nbr_classes.unwrap_or(0),
         fitness_func_type: fitness_type,
+        connection_sampling: args.connection_sampling,
+        multiclass_decoding: args.multiclass_decoding,
+        class_costs: class_costs.clone(),
     };
 
-    // let stdout = std::io::stdout();
-    // let mut lock = stdout.lock();
-
-    let mut cross_validate = CrossValidation::new(data.len(), 5);
+    let redundant_inputs = redundant_input_ids(&indice_insert_copy_mapping);
+    let attribute_labels = AttributeLabels::new(data[0].len(), &indice_insert_copy_mapping, redundancy_type);
+
+    let mut cross_validate = CrossValidation::new(data.len(), NBR_FOLDS, &mut rng);
+
+    // splits and seeds are drawn up front, so the folds give the same results
+    // regardless of the order in which they are executed
+    let folds: Vec<_> = (0..NBR_FOLDS)
+        .map(|fold| {
+            let test_indices = cross_validate.get_fold_indices(fold).clone();
+            (cross_validate.split(data.clone(), label.clone()), rng.gen::<u64>(), test_indices)
+        })
+        .collect();
+
+    // unlike the fixed test sets of credit and shuttle, a fold may hold the only samples of a rare class
+    if nbr_classes.is_some() {
+        for (fold, ((_, train_label, _, test_label), _, _)) in folds.iter().enumerate() {
+            let unseen = unseen_labels(train_label, test_label);
+            if !unseen.is_empty() {
+                let unseen: Vec<&str> = unseen.iter().map(|class| label_encoder.decode(*class)).collect();
+                eprintln!("warning: test labels {:?} of fold {} do not occur in its training data", unseen, fold);
+            }
+        }
+    }
 
-    for fold in 0..5 {
+    folds.into_par_iter().enumerate().for_each(|(fold, (split, fold_seed, test_indices))| {
         let run_id = args.run_id + fold;
 
-        let (train_data, train_label, test_data, test_label) = cross_validate.split(data.clone(), label.clone());
+        if args.resume && args.is_fold_completed(fold) {
+            return;
+        }
+
+        let (train_data, train_label, test_data, test_label) = split;
         let test_data = Some(test_data);
         let test_label = Some(test_label);
 
         // ################################################################################
         // ############################ Logger ####### ####################################
         // ################################################################################
-        let dataset_string = match args.dataset {
-            0 => "abalone",
-            1 => "credit",
-            2 => "shuttle",
-            3 => "breast_cancer",
-            4 => "page_blocks",
-            5 => "waveform",
-            _ => panic!("wrong dataset number in string"),
-        };
-
-        let redundancy_string = match args.redundancy_type {
-            0 => "redundancy_type_0",
-            1 => "redundancy_type_1",
-            2 => "redundancy_type_2",
-            _ => panic!("wrong redundancy type"),
-        };
-        let databloat_string = match args.data_bloat {
-            0.0 => "baseline",
-            0.2 => "databloat_20",
-            0.4 => "databloat_40",
-            0.6 => "databloat_60",
-            0.8 => "databloat_80",
-            1.0 => "databloat_100",
-            _ => panic!("wrong datablaot type"),
-        };
-
-        let save_path:PathBuf;
-        if args.data_bloat == 0.0 {
-            save_path = Path::new("")
-                .join("Experiments_Output")
-                .join(dataset_string)
-                .join(databloat_string)
-                .join(format!("number_nodes_{}", args.nbr_nodes));
-        } else {
-            save_path = Path::new("")
-                .join("Experiments_Output")
-                .join(dataset_string)
-                .join(redundancy_string)
-                .join(databloat_string)
-                .join(format!("number_nodes_{}", args.nbr_nodes));
-        }
+        let save_path = args.save_path();
 
         fs::create_dir_all(save_path.clone()).unwrap();
 
-        let save_file_iteration = format!("run_{}_iteration.txt", run_id);
-        let mut output_file = BufWriter::new(File::create(save_path.join(save_file_iteration))
-            .expect("cannot create file"));
+        let save_file_log = RunLogger::path(&save_path, run_id, args.log_format);
+        let checkpoint_path = Checkpoint::path(&save_path, run_id);
+        let save_file_genomes = save_path.join(format!("run_{}_genomes.txt.gz", run_id));
+
         // ################################################################################
         // ############################ Training ##########################################
         // ################################################################################
+        let checkpoint = match args.resume {
+            true => Checkpoint::load(&checkpoint_path),
+            false => None,
+        };
 
+        let (mut runner, mut run_logger, mut genome_logger, time_offset) = match checkpoint {
+            Some(checkpoint) => {
+                // the runner keeps the settings it was created with, a different configuration would be ignored
+                let differing = match &checkpoint.config {
+                    Some(config) => args.differing_settings(config),
+                    None => panic!("{} does not record its configuration, remove it to start the fold again",
+                                   checkpoint_path.display()),
+                };
+                if !differing.is_empty() {
+                    panic!("{} was written with other settings {:?}, resume with the same settings",
+                           checkpoint_path.display(), differing);
+                }
+                assert_eq!(checkpoint.seed, args.seed, "checkpoint was written with a different seed");
+                assert_eq!(checkpoint.fold, fold);
+                assert_eq!(checkpoint.test_indices, test_indices, "checkpoint does not match the fold");
+
+                let mut runner = checkpoint.runner;
+                runner.attach_data(train_data, train_label, test_data, test_label);
+
+                // drop the records logged after the checkpoint, they are repeated
+                let run_logger = RunLogger::resume(save_file_log.clone(), args.log_format, checkpoint.run_log_len);
+
+                let genome_logger = match checkpoint.genome_log_len {
+                    Some(len) if args.log_genomes > 0 => Some(LoggingStuff::resume(save_file_genomes.clone(), len)),
+                    _ => None,
+                };
+
+                (runner, run_logger, genome_logger, Duration::from_secs_f64(checkpoint.elapsed_seconds))
+            }
+            None => {
+                let mut runner = Runner::new(params.clone(),
+                                             train_data,
+                                             train_label,
+                                             test_data,
+                                             test_label,
+                                             fold_seed);
+                runner.track_input_usage(args.track_input_usage);
+                runner.set_mutation_strategy(args.mutation);
+                runner.set_rate_control(args.rate_control);
+                runner.set_input_penalty(InputPenaltyTerm::new(args.input_penalty, &attribute_labels));
+                runner.set_selection(args.selection);
+                let run_logger = RunLogger::new(save_file_log.clone(), args.log_format);
+
+                let genome_logger = match args.log_genomes {
+                    0 => None,
+                    _ => Some(LoggingStuff::new(save_file_genomes.clone())),
+                };
+
+                (runner, run_logger, genome_logger, Duration::ZERO)
+            }
+        };
+        let start_time = Instant::now();
+
+        let iteration_record = |runner: &Runner, elapsed: Duration| {
+            let parent = runner.get_parent();
+            IterationRecord {
+                iteration: runner.get_iteration(),
+                train_fitness: runner.get_parent_error(),
+                active_nodes: parent.active_nodes.len(),
+                redundant_inputs: parent.active_nodes
+                    .iter()
+                    .filter(|node_id| redundant_inputs.contains(node_id))
+                    .count(),
+                elapsed_seconds: elapsed.as_secs_f64(),
+                mutation_rate: runner.get_mutation_rate(),
+                input_penalty: runner.get_parent_input_penalty(),
+            }
+        };
 
-        let mut runner = Runner::new(params.clone(),
-                                     train_data,
-                                     train_label,
-                                     test_data,
-                                     test_label);
-
-        let mut runtime_iterations: usize = 0;
-
-        for _ in 0..100_000 {
-            writeln!(output_file, "Iteration: {runtime_iterations}, Fitness: {:?}", runner.get_best_fitness()).expect("write not okay??");
-            // if runtime_iterations % params.eval_after_iterations == 0 {
-            //     writeln!(lock, "Iteration: {runtime_iterations}, Fitness: {:?}", runner.get_best_fitness()).expect("write not okay??");
-            // }
-            runtime_iterations += 1;
+        // the stopping criteria are checked after every iteration, so a fold always runs at least one
+        let stop_reason = loop {
+            if params.eval_after_iterations > 0 && runner.get_iteration() % params.eval_after_iterations == 0 {
+                run_logger.log(&iteration_record(&runner, time_offset + start_time.elapsed()));
+            }
+            if let Some(logger) = genome_logger.as_mut() {
+                if runner.get_iteration() % args.log_genomes == 0 {
+                    logger.execute(runner.get_iteration(), &runner.get_parent());
+                }
+            }
             runner.learn_step();  // lern step
 
-            if runner.get_best_fitness() <= 0.01 {  // for single parent
-                // println!("eval fitness: {:?}", runner.get_test_fitness());
-                break;
+            if args.checkpoint_interval > 0 && runner.get_iteration() % args.checkpoint_interval == 0 {
+                let checkpoint = Checkpoint {
+                    seed: args.seed,
+                    fold,
+                    test_indices: test_indices.clone(),
+                    run_log_len: run_logger.checkpoint(),
+                    elapsed_seconds: (time_offset + start_time.elapsed()).as_secs_f64(),
+                    genome_log_len: genome_logger.as_mut().map(|logger| logger.checkpoint()),
+                    config: Some(args.clone()),
+                    runner,
+                };
+                checkpoint.save(&checkpoint_path);
+                runner = checkpoint.runner;
+            }
+
+            if let Some(reason) = args.stopping_criteria.check(&runner, time_offset + start_time.elapsed()) {
+                break reason;
             }
+        };
+        let runtime_iterations = runner.get_iteration();
+        let elapsed = time_offset + start_time.elapsed();
+
+        // the final state is always logged
+        run_logger.log(&iteration_record(&runner, elapsed));
+        run_logger.close_writer();
+        if let Some(mut logger) = genome_logger {
+            logger.execute(runtime_iterations, &runner.get_parent());
+            logger.close_writer()
+                .unwrap_or_else(|e| panic!("cannot write genome log {}: {}", save_file_genomes.display(), e));
+        }
+
+        if args.track_input_usage > 0 {
+            let usage = usage_over_time(runner.get_input_usage_history(), &attribute_labels);
+            usage.write_csv(&save_path.join(format!("run_{}_input_usage.csv", run_id)));
         }
 
         let fitness_eval = runner.get_test_fitness();
-        let fitness_train = runner.get_best_fitness();
+        let fitness_train = runner.get_parent_error();
 
         // ################################################################################
-        // ############################ Saving to text ####################################
+        // ############################ Saving ############################################
         // ################################################################################
         println!("{fitness_train}");
-
-        let save_file_redundancy = format!("run_{}_redundancy.txt", run_id);
-        let mut output_redundancy = File::create(save_path.join(save_file_redundancy))
-            .expect("cannot create file");
-        write!(output_redundancy, "{:?}", indice_insert_copy_mapping).expect("cannot write");
-
         println!("{runtime_iterations}");
-        writeln!(output_file, "End at iteration: {}", runtime_iterations).expect("cannot write");
-        writeln!(output_file, "Fitness Eval: {}", fitness_eval).expect("cannot write");
-        writeln!(output_file, "Fitness Train: {}", fitness_train).expect("cannot write");
-
-        output_file.flush().unwrap();
-
-        let save_file_active_node = format!("run_{}_active_node.txt", run_id);
-        let mut output = File::create(save_path.join(save_file_active_node))
-            .expect("cannot create file");
 
         let mut parent = runner.get_parent();
         parent.get_active_nodes_id();
+        fs::write(save_path.join(format!("run_{}_graph.dot", run_id)), parent.to_dot(Some(&attribute_labels), false))
+            .expect("cannot write graph");
+
+        let summary = RunSummary {
+            run_id,
+            fold,
+            seed: args.seed,
+            dataset: args.dataset_string().to_string(),
+            data_bloat: args.data_bloat,
+            redundancy_type: args.redundancy_type,
+            nbr_nodes: args.nbr_nodes,
+            rows: args.rows,
+            levels_back: args.levels_back,
+            mutation: args.mutation,
+            rate_control: args.rate_control,
+            connection_sampling: args.connection_sampling,
+            multiclass_decoding: args.multiclass_decoding,
+            class_costs: class_costs.clone(),
+            selection: args.selection,
+            input_penalty: args.input_penalty,
+            iterations: runtime_iterations,
+            fitness_train,
+            input_penalty_train: runner.get_parent_input_penalty(),
+            fitness_eval,
+            stop_reason: stop_reason.to_string(),
+            elapsed_seconds: elapsed.as_secs_f64(),
+            attribute_usage: attribute_usage(&parent, &attribute_labels),
+            expressions: parent.to_expressions(true, false),
+            classes: label_encoder.classes.clone(),
+            active_nodes: parent.active_nodes,
+            redundancy_mapping: indice_insert_copy_mapping
+                .iter()
+                .map(|(key, val)| (*key, val.clone()))
+                .collect(),
+            config: Some(args.clone()),
+        };
+        summary.save(&RunSummary::path(&save_path, run_id));
+
+        if let Selection::Nsga2 { .. } = args.selection {
+            let front = ParetoFront {
+                solutions: runner
+                    .get_pareto_front()
+                    .into_iter()
+                    .map(|(chromosome, objectives)| ParetoSolution {
+                        objectives,
+                        post_hoc_fitness_eval: runner.get_test_fitness_of(&chromosome),
+                        expressions: chromosome.to_expressions(true, false),
+                        active_nodes: chromosome.active_nodes,
+                    })
+                    .collect(),
+            };
+            front.save(&ParetoFront::path(&save_path, run_id));
+        }
+
+        let model = Model::new(runner.get_parent(), scaler.clone(), attribute_labels.clone(), Some(label_encoder.clone()));
+        model.save(&Model::path(&save_path, run_id));
+
+        if let Some(nbr_classes) = nbr_classes {
+            let (train_predictions, train_labels) = runner.get_train_predictions();
+            let report = FoldReport {
+                train: ClassificationReport::new(&train_predictions, train_labels, nbr_classes),
+                test: runner.get_test_predictions().map(|(test_predictions, test_labels)| {
+                    ClassificationReport::new(&test_predictions, test_labels, nbr_classes)
+                }),
+                class_labels: label_encoder.classes.clone(),
+            };
+            report.save(&FoldReport::path(&save_path, run_id));
+        }
 
-        write!(output, "{:?}", parent.active_nodes).expect("cannot write");
+        // the fold is complete, its checkpoint is not needed anymore
+        if checkpoint_path.exists() {
+            fs::remove_file(&checkpoint_path).expect("cannot remove checkpoint");
+        }
+    });
+
+    // ################################################################################
+    // ############################ Cross Validation Report ###########################
+    // ################################################################################
+    let save_path = args.save_path();
+    let report_paths: Vec<PathBuf> = (0..NBR_FOLDS)
+        .map(|fold| FoldReport::path(&save_path, args.run_id + fold))
+        .collect();
+    // folds completed by an older version have no report
+    if nbr_classes.is_some() && report_paths.iter().all(|path| path.exists()) {
+        let fold_reports: Vec<FoldReport> = report_paths.iter().map(|path| FoldReport::load(path)).collect();
+        let report = CrossValidationReport::new(&fold_reports);
+        report.save(&CrossValidationReport::path(&save_path, args.run_id, NBR_FOLDS));
     }
 }
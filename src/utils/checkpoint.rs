@@ -0,0 +1,58 @@
+use std::fs;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use crate::utils::experiment::RunConfig;
+use crate::utils::runner::Runner;
+
+/// Everything needed to continue a fold exactly where it stopped.
+/// The datasets are not stored; they are rebuilt from the seed, which reproduces the same fold.
+#[derive(Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub seed: u64,
+    pub fold: usize,
+    /// indices of the test samples of this fold, to verify that the rebuilt fold is the same
+    pub test_indices: Vec<usize>,
+    /// length in bytes of the run log when the checkpoint was written
+    pub run_log_len: u64,
+    /// wall-clock time spent on the fold so far
+    pub elapsed_seconds: f64,
+    /// length in bytes of the genome log, if it is enabled
+    #[serde(default)]
+    pub genome_log_len: Option<u64>,
+    /// configuration the fold was started with; None for older checkpoints
+    #[serde(default)]
+    pub config: Option<RunConfig>,
+    pub runner: Runner,
+}
+
+impl Checkpoint {
+    pub fn path(save_path: &Path, run_id: usize) -> PathBuf {
+        return save_path.join(format!("run_{}_checkpoint.json", run_id));
+    }
+
+    /// Writes to a temporary file first, so a crash while saving does not destroy the last checkpoint.
+    pub fn save(&self, path: &Path) {
+        let tmp_path = path.with_extension("json.tmp");
+
+        let mut writer = BufWriter::new(File::create(&tmp_path)
+            .expect("cannot create checkpoint file"));
+        serde_json::to_writer(&mut writer, self).expect("cannot write checkpoint");
+        writer.flush().expect("cannot write checkpoint");
+        drop(writer);
+
+        fs::rename(&tmp_path, path).expect("cannot move checkpoint file");
+    }
+
+    pub fn load(path: &Path) -> Option<Self> {
+        if !path.exists() {
+            return None;
+        }
+
+        let reader = BufReader::new(File::open(path).expect("cannot open checkpoint file"));
+        let checkpoint: Checkpoint = serde_json::from_reader(reader)
+            .unwrap_or_else(|e| panic!("corrupt checkpoint {}: {}", path.display(), e));
+        return Some(checkpoint);
+    }
+}
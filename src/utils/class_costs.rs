@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::datasets::dataset_utils::LabelEncoder;
+use crate::utils::fitness_metrics::{fitness_class_weighted, fitness_cost_matrix};
+
+/// Misclassification costs as written in a TOML or JSON file, keyed by the original class labels:
+/// ```toml
+/// type = "matrix"
+/// [costs.M]  # true class
+/// B = 5.0    # predicted class
+/// ```
+/// or
+/// ```toml
+/// type = "weights"
+/// [weights]
+/// M = 2.0
+/// ```
+/// Missing costs are 1 for a misclassification and 0 for a correct prediction, missing weights are 1.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClassCostsFile {
+    Weights {
+        weights: HashMap<String, f32>,
+    },
+    Matrix {
+        costs: HashMap<String, HashMap<String, f32>>,
+    },
+}
+
+/// Cost-sensitive fitness of a classification run, replacing the balanced error (multiclass)
+/// and the error rate (binary). Indexed by the class ids of the `LabelEncoder`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClassCosts {
+    /// weight of the samples of every class, see `fitness_class_weighted`
+    Weights {
+        weights: Vec<f32>,
+    },
+    /// `costs[true class][predicted class]`, see `fitness_cost_matrix`
+    Matrix {
+        costs: Vec<Vec<f32>>,
+    },
+}
+
+impl ClassCosts {
+    /// Reads a cost file and maps its labels to the class ids of `label_encoder`.
+    /// Panics on labels that are not classes of the dataset and on negative or non-finite costs.
+    pub fn load(path: &Path, label_encoder: &LabelEncoder) -> Self {
+        let contents = fs::read_to_string(path)
+            .unwrap_or_else(|e| panic!("cannot read class costs {}: {}", path.display(), e));
+
+        let file: ClassCostsFile = match path.extension().and_then(|x| x.to_str()) {
+            Some("json") => serde_json::from_str(&contents).expect("invalid json class costs"),
+            Some("toml") => toml::from_str(&contents).expect("invalid toml class costs"),
+            _ => panic!("class costs file must be .toml or .json: {}", path.display()),
+        };
+        return ClassCosts::from_file(file, label_encoder);
+    }
+
+    fn from_file(file: ClassCostsFile, label_encoder: &LabelEncoder) -> Self {
+        let class_id = |label: &str| label_encoder
+            .encode(label)
+            .unwrap_or_else(|| panic!("unknown class {} in class costs, expected one of {:?}", label, label_encoder.classes));
+        let nbr_classes = label_encoder.nbr_classes();
+
+        let class_costs = match file {
+            ClassCostsFile::Weights { weights: labelled_weights } => {
+                let mut weights = vec![1.; nbr_classes];
+                for (label, weight) in labelled_weights {
+                    weights[class_id(&label)] = weight;
+                }
+                ClassCosts::Weights {
+                    weights,
+                }
+            }
+            ClassCostsFile::Matrix { costs: labelled_costs } => {
+                let mut costs: Vec<Vec<f32>> = (0..nbr_classes)
+                    .map(|true_class| (0..nbr_classes)
+                        .map(|predicted_class| if true_class == predicted_class { 0. } else { 1. })
+                        .collect())
+                    .collect();
+                for (true_label, row) in labelled_costs {
+                    let true_class = class_id(&true_label);
+                    for (predicted_label, cost) in row {
+                        costs[true_class][class_id(&predicted_label)] = cost;
+                    }
+                }
+                ClassCosts::Matrix {
+                    costs,
+                }
+            }
+        };
+
+        assert!(class_costs.values().iter().all(|cost| cost.is_finite() && *cost >= 0.),
+                "class costs must be finite and not negative: {:?}", class_costs);
+        return class_costs;
+    }
+
+    fn values(&self) -> Vec<f32> {
+        return match self {
+            ClassCosts::Weights { weights } => weights.clone(),
+            ClassCosts::Matrix { costs } => costs.iter().flatten().copied().collect(),
+        };
+    }
+
+    pub fn fitness(&self, prediction: &Vec<usize>, labels: &Vec<usize>) -> f32 {
+        return match self {
+            ClassCosts::Weights { weights } => fitness_class_weighted(prediction, labels, weights),
+            ClassCosts::Matrix { costs } => fitness_cost_matrix(prediction, labels, costs),
+        };
+    }
+}
@@ -0,0 +1,92 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use crate::utils::utility_funcs::gen_random_number_in_range;
+
+/// Distribution of the connection genes, used when creating and when mutating nodes.
+/// Node ids are ordered inputs first, then computational nodes.
+/// All variants only choose among the nodes allowed by `rows` and `levels_back` of the grid.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ConnectionSampling {
+    /// every earlier node is equally likely
+    Uniform,
+    /// any input node or one of the `window` computational nodes directly before the node,
+    /// all equally likely. Output nodes may connect to every node.
+    /// Unlike `levels_back` of the grid, the window counts nodes, not columns, and keeps the inputs reachable
+    RecentNodes {
+        window: usize,
+    },
+    /// an input node with probability `input_probability`, otherwise an earlier computational node;
+    /// uniform within both groups. Keeps the chance of connecting to an input independent of the number of attributes
+    InputSplit {
+        input_probability: f32,
+    },
+}
+
+impl Default for ConnectionSampling {
+    fn default() -> Self {
+        return ConnectionSampling::Uniform;
+    }
+}
+
+impl ConnectionSampling {
+    /// The sampling used for output nodes.
+    pub fn for_outputs(&self) -> Self {
+        return match self {
+            ConnectionSampling::RecentNodes { .. } => ConnectionSampling::Uniform,
+            other => *other,
+        };
+    }
+
+    /// Samples a connection to one of the nodes `lowest..highest`, the ids allowed by the grid layout
+    /// (see `Node::connection_range`). When mutating, `current` is the old value;
+    /// the new one differs from it unless it is the only choice.
+    pub fn sample<R: Rng>(&self,
+                          lowest: usize,
+                          highest: usize,
+                          nbr_inputs: usize,
+                          current: Option<usize>,
+                          rng: &mut R) -> usize {
+        if let ConnectionSampling::Uniform = self {
+            return match current {
+                None => rng.gen_range(lowest..highest),
+                Some(current) => gen_random_number_in_range(current, lowest, highest, rng),
+            };
+        }
+
+        // the grid either allows all inputs or none of them
+        let nbr_allowed_inputs = nbr_inputs.min(highest).saturating_sub(lowest);
+        let first_internal = match *self {
+            ConnectionSampling::RecentNodes { window } => nbr_inputs.max(lowest).max(highest.saturating_sub(window)),
+            _ => nbr_inputs.max(lowest),
+        };
+        let nbr_internal = highest.saturating_sub(first_internal);
+
+        let input_probability = match (*self, nbr_allowed_inputs, nbr_internal) {
+            (_, _, 0) => 1.,
+            (_, 0, _) => 0.,
+            // all candidates equally likely
+            (ConnectionSampling::RecentNodes { .. }, _, _) => nbr_allowed_inputs as f32 / (nbr_allowed_inputs + nbr_internal) as f32,
+            (ConnectionSampling::InputSplit { input_probability }, _, _) => input_probability.clamp(0., 1.),
+            (ConnectionSampling::Uniform, _, _) => unreachable!(),
+        };
+        let nbr_reachable = match input_probability {
+            p if p >= 1. => nbr_allowed_inputs,
+            p if p <= 0. => nbr_internal,
+            _ => nbr_allowed_inputs + nbr_internal,
+        };
+
+        loop {
+            let connection = if rng.gen::<f32>() < input_probability {
+                rng.gen_range(lowest..lowest + nbr_allowed_inputs)
+            } else {
+                rng.gen_range(first_internal..highest)
+            };
+
+            match current {
+                Some(current) if connection == current && nbr_reachable > 1 => continue,
+                _ => return connection,
+            }
+        }
+    }
+}
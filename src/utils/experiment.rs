@@ -0,0 +1,349 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use crate::global_params::default_rows;
+use crate::utils::connection_sampling::ConnectionSampling;
+use crate::utils::multiclass_decoding::MulticlassDecoding;
+use crate::utils::input_penalty::InputPenalty;
+use crate::utils::mutation_strategy::{MutationStrategy, RateControl};
+use crate::utils::run_logger::{LogFormat, RunSummary};
+use crate::utils::selection::Selection;
+use crate::utils::stopping_criteria::StoppingCriteria;
+
+pub const NBR_FOLDS: usize = 5;
+
+/// One dataset / bloat / redundancy type / nbr_nodes / seed combination.
+/// Each configuration is trained on all folds of a cross validation.
+/// It is stored with the results; the settings that only control logging and checkpoints are not.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RunConfig {
+    pub run_id: usize,
+    pub dataset: usize,
+    pub dataset_path: String,
+    pub nbr_nodes: usize,
+    /// rows of the grid; the `nbr_nodes` computational nodes form `nbr_nodes / rows` columns
+    pub rows: usize,
+    /// preceding columns a node may connect to; None allows all of them
+    pub levels_back: Option<usize>,
+    pub data_bloat: f32,
+    pub redundancy_type: usize,
+    pub seed: u64,
+    pub stopping_criteria: StoppingCriteria,
+    /// write a checkpoint every n iterations; 0 disables checkpoints
+    #[serde(skip)]
+    pub checkpoint_interval: usize,
+    /// continue folds from their checkpoints and skip completed folds
+    #[serde(skip)]
+    pub resume: bool,
+    /// log the genome of the parent every n iterations; 0 disables the log
+    #[serde(skip)]
+    pub log_genomes: usize,
+    #[serde(skip, default = "default_log_format")]
+    pub log_format: LogFormat,
+    /// write a record to the run log every n iterations; 0 only logs the final iteration
+    #[serde(skip)]
+    pub eval_after_iterations: usize,
+    /// record the active inputs of the parent every n iterations; 0 disables the recording
+    #[serde(skip)]
+    pub track_input_usage: usize,
+    pub mutation: MutationStrategy,
+    pub rate_control: RateControl,
+    pub connection_sampling: ConnectionSampling,
+    pub multiclass_decoding: MulticlassDecoding,
+    /// TOML or JSON file with misclassification costs or class weights, see `ClassCosts`
+    pub class_costs: Option<PathBuf>,
+    pub selection: Selection,
+    pub input_penalty: InputPenalty,
+}
+
+impl RunConfig {
+    pub fn dataset_string(&self) -> &'static str {
+        return match self.dataset {
+            0 => "abalone",
+            1 => "credit",
+            2 => "shuttle",
+            3 => "breast_cancer",
+            4 => "page_blocks",
+            5 => "waveform",
+            _ => panic!("wrong dataset number in string"),
+        };
+    }
+
+    pub fn redundancy_string(&self) -> String {
+        return format!("redundancy_type_{}", self.redundancy_type);
+    }
+
+    /// 0.0 -> baseline, 0.2 -> databloat_20, ...
+    pub fn databloat_string(&self) -> String {
+        if self.data_bloat == 0.0 {
+            return "baseline".to_string();
+        }
+        return format!("databloat_{}", (self.data_bloat * 100.).round() as usize);
+    }
+
+    /// Runs with other settings than those in the path get their own `settings_<id>` directory, see `settings_id`.
+    pub fn save_path(&self) -> PathBuf {
+        let settings = format!("settings_{}", self.settings_id());
+        if self.data_bloat == 0.0 {
+            return Path::new("")
+                .join("Experiments_Output")
+                .join(self.dataset_string())
+                .join(self.databloat_string())
+                .join(format!("number_nodes_{}", self.nbr_nodes))
+                .join(settings);
+        } else {
+            return Path::new("")
+                .join("Experiments_Output")
+                .join(self.dataset_string())
+                .join(self.redundancy_string())
+                .join(self.databloat_string())
+                .join(format!("number_nodes_{}", self.nbr_nodes))
+                .join(settings);
+        }
+    }
+
+    /// Stored settings that the baseline and the redundancy arms of one experiment share:
+    /// all but the dataset file, the bloat, the redundancy type, the seed and the run id.
+    pub fn shared_settings(&self) -> serde_json::Map<String, serde_json::Value> {
+        let mut settings = match serde_json::to_value(self).expect("cannot serialize config") {
+            serde_json::Value::Object(settings) => settings,
+            _ => panic!("config is not serialized as a map"),
+        };
+        for key in ["dataset_path", "data_bloat", "redundancy_type", "seed", "run_id"] {
+            settings.remove(key);
+        }
+        return settings;
+    }
+
+    /// Hash of `shared_settings` as 16 hex digits. FNV-1a is used as, unlike `DefaultHasher`,
+    /// it does not change between Rust versions.
+    pub fn settings_id(&self) -> String {
+        let settings = serde_json::to_string(&self.shared_settings()).expect("cannot serialize config");
+        let hash = settings
+            .bytes()
+            .fold(0xcbf29ce484222325u64, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3));
+        return format!("{:016x}", hash);
+    }
+
+    /// Panics on combinations of settings that cannot be run.
+    pub fn validate(&self) {
+        if self.rate_control != RateControl::Fixed && self.mutation.rate().is_none() {
+            panic!("rate control {:?} needs point or genewise mutation", self.rate_control);
+        }
+        if self.rate_control != RateControl::Fixed && matches!(self.selection, Selection::Nsga2 { .. }) {
+            panic!("rate control {:?} cannot be used with nsga2 selection", self.rate_control);
+        }
+    }
+
+    /// Names of the settings in which `other` differs from this configuration.
+    /// Settings that only control logging and checkpoints are ignored.
+    pub fn differing_settings(&self, other: &RunConfig) -> Vec<String> {
+        let this = serde_json::to_value(self).expect("cannot serialize config");
+        let other = serde_json::to_value(other).expect("cannot serialize config");
+
+        return this
+            .as_object()
+            .expect("config is not serialized as a map")
+            .iter()
+            .filter(|(key, value)| other.get(key.as_str()) != Some(value))
+            .map(|(key, _)| key.clone())
+            .collect();
+    }
+
+    /// A fold is complete once its summary is written, as it is saved last.
+    /// Panics if the summary was written with other settings, as the runs would share their results.
+    pub fn is_fold_completed(&self, fold: usize) -> bool {
+        let path = RunSummary::path(&self.save_path(), self.run_id + fold);
+        if !path.exists() {
+            return false;
+        }
+
+        let differing = match RunSummary::load(&path).config {
+            Some(config) => self.differing_settings(&config),
+            None => panic!("{} does not record its configuration, remove it to run the fold again", path.display()),
+        };
+        if !differing.is_empty() {
+            panic!("{} was written with other settings {:?}, remove it or use other settings", path.display(), differing);
+        }
+        return true;
+    }
+
+    pub fn is_completed(&self) -> bool {
+        return (0..NBR_FOLDS).all(|fold| self.is_fold_completed(fold));
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct GridDataset {
+    pub id: usize,
+    pub path: String,
+    /// misclassification costs or class weights of this dataset, see `ClassCosts`
+    #[serde(default)]
+    pub class_costs: Option<PathBuf>,
+}
+
+/// Parameter grid for the `experiment` subcommand, read from a TOML or JSON file:
+/// ```toml
+/// data_bloat = [0.0, 0.2, 0.4]
+/// redundancy_types = [0, 1, 2]
+/// nbr_nodes = [500]
+/// seeds = [0, 1, 2]
+/// rows = 1  # optional, default: 1
+/// levels_back = 100  # optional, default: all columns
+/// multiclass_decoding = "softmax"  # optional, see `MulticlassDecoding`; default: argmax
+///
+/// [stopping_criteria]  # optional, see `StoppingCriteria`
+/// max_iterations = 100000
+/// target_fitness = 0.01
+///
+/// [mutation]  # optional, see `MutationStrategy`; default: single_active
+/// type = "point"
+/// rate = 0.05
+/// force_active = true
+///
+/// [rate_control]  # optional, see `RateControl`; default: fixed
+/// type = "one_fifth"
+/// window = 10
+/// factor = 1.5
+/// min_rate = 0.001
+/// max_rate = 0.5
+///
+/// [connection_sampling]  # optional, see `ConnectionSampling`; default: uniform
+/// type = "input_split"
+/// input_probability = 0.25
+///
+/// [selection]  # optional, see `Selection`; default: fitness
+/// type = "nsga2"
+/// population_size = 50
+///
+/// [input_penalty]  # optional, see `InputPenalty`; default: disabled
+/// weight = 0.001
+/// count_copies_once = true
+///
+/// [[datasets]]
+/// id = 3
+/// path = "src/datasets/data/breast+cancer+wisconsin+diagnostic.data"
+/// class_costs = "costs.toml"  # optional, see `ClassCosts`
+/// ```
+#[derive(Deserialize, Clone, Debug)]
+pub struct ExperimentGrid {
+    pub datasets: Vec<GridDataset>,
+    pub data_bloat: Vec<f32>,
+    pub redundancy_types: Vec<usize>,
+    pub nbr_nodes: Vec<usize>,
+    pub seeds: Vec<u64>,
+    #[serde(default = "default_rows")]
+    pub rows: usize,
+    #[serde(default)]
+    pub levels_back: Option<usize>,
+    #[serde(default)]
+    pub stopping_criteria: StoppingCriteria,
+    #[serde(default = "default_checkpoint_interval")]
+    pub checkpoint_interval: usize,
+    #[serde(default)]
+    pub log_genomes: usize,
+    #[serde(default = "default_log_format")]
+    pub log_format: LogFormat,
+    #[serde(default = "default_eval_after_iterations")]
+    pub eval_after_iterations: usize,
+    #[serde(default)]
+    pub track_input_usage: usize,
+    #[serde(default)]
+    pub mutation: MutationStrategy,
+    #[serde(default)]
+    pub rate_control: RateControl,
+    #[serde(default)]
+    pub connection_sampling: ConnectionSampling,
+    #[serde(default)]
+    pub multiclass_decoding: MulticlassDecoding,
+    #[serde(default)]
+    pub selection: Selection,
+    #[serde(default)]
+    pub input_penalty: InputPenalty,
+}
+
+fn default_checkpoint_interval() -> usize {
+    return 10_000;
+}
+
+fn default_log_format() -> LogFormat {
+    return LogFormat::Jsonl;
+}
+
+fn default_eval_after_iterations() -> usize {
+    return 500;
+}
+
+impl ExperimentGrid {
+    pub fn load(path: &Path) -> Self {
+        let contents = fs::read_to_string(path)
+            .expect("Should have been able to read the grid file");
+
+        return match path.extension().and_then(|x| x.to_str()) {
+            Some("json") => serde_json::from_str(&contents).expect("invalid json grid"),
+            Some("toml") => toml::from_str(&contents).expect("invalid toml grid"),
+            _ => panic!("grid file must be .toml or .json: {}", path.display()),
+        };
+    }
+
+    /// Panics on invalid runs and on runs that would write the same files, e.g. two bloats
+    /// rounding to the same percentage or two datasets with the same id.
+    pub fn validate(&self) {
+        let mut outputs: HashSet<(PathBuf, usize)> = HashSet::new();
+        for config in self.runs() {
+            config.validate();
+            if !outputs.insert((config.save_path(), config.run_id)) {
+                panic!("the grid writes run {} in {} more than once", config.run_id, config.save_path().display());
+            }
+        }
+    }
+
+    /// All combinations of the grid. The n-th seed writes the runs `n * NBR_FOLDS..(n + 1) * NBR_FOLDS`.
+    /// Interrupted runs are resumed from their checkpoints.
+    /// The baseline (no bloat) does not depend on the redundancy type, so it is only scheduled once.
+    pub fn runs(&self) -> Vec<RunConfig> {
+        let mut runs: Vec<RunConfig> = vec![];
+
+        for dataset in &self.datasets {
+            for data_bloat in &self.data_bloat {
+                for (i, redundancy_type) in self.redundancy_types.iter().enumerate() {
+                    if *data_bloat == 0.0 && i > 0 {
+                        continue;
+                    }
+                    for nbr_nodes in &self.nbr_nodes {
+                        for (seed_index, seed) in self.seeds.iter().enumerate() {
+                            runs.push(RunConfig {
+                                run_id: seed_index * NBR_FOLDS,
+                                dataset: dataset.id,
+                                dataset_path: dataset.path.clone(),
+                                nbr_nodes: *nbr_nodes,
+                                rows: self.rows,
+                                levels_back: self.levels_back,
+                                data_bloat: *data_bloat,
+                                redundancy_type: *redundancy_type,
+                                seed: *seed,
+                                stopping_criteria: self.stopping_criteria.clone(),
+                                checkpoint_interval: self.checkpoint_interval,
+                                resume: true,
+                                log_genomes: self.log_genomes,
+                                log_format: self.log_format,
+                                eval_after_iterations: self.eval_after_iterations,
+                                track_input_usage: self.track_input_usage,
+                                mutation: self.mutation,
+                                rate_control: self.rate_control,
+                                connection_sampling: self.connection_sampling,
+                                multiclass_decoding: self.multiclass_decoding,
+                                class_costs: dataset.class_costs.clone(),
+                                selection: self.selection,
+                                input_penalty: self.input_penalty,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        return runs;
+    }
+}
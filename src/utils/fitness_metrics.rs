@@ -67,6 +67,102 @@ pub fn fitness_categorical_multiclass(prediction: &Vec<usize>, labels: &Vec<usiz
     return 1. - balanced_accuracy;
 }
 
+/// Mean misclassification cost `costs[true class][predicted class]`, divided by the largest cost so it lies in [0, 1].
+/// With equal costs for all misclassifications and 0 for correct predictions this is the error rate.
+/// Predictions without a column in `costs` get the largest cost.
+pub fn fitness_cost_matrix(prediction: &Vec<usize>, labels: &Vec<usize>, costs: &Vec<Vec<f32>>) -> f32 {
+    let max_cost = costs.iter().flatten().fold(0f32, |max, cost| max.max(*cost));
+    if max_cost <= 0. {
+        return 0.;
+    }
+
+    let mut total_cost = 0.;
+    prediction.iter().zip(labels.iter()).for_each(|(x, y)| {
+        total_cost += costs[*y].get(*x).copied().unwrap_or(max_cost);
+    });
+
+    return total_cost / (labels.len() as f32 * max_cost);
+}
+
+/// Error rate with every sample weighted by `weights[true class]`.
+/// With equal weights this is the error rate.
+pub fn fitness_class_weighted(prediction: &Vec<usize>, labels: &Vec<usize>, weights: &Vec<f32>) -> f32 {
+    let mut misclassified_weight = 0.;
+    let mut total_weight = 0.;
+    prediction.iter().zip(labels.iter()).for_each(|(x, y)| {
+        total_weight += weights[*y];
+        if x != y {
+            misclassified_weight += weights[*y];
+        }
+    });
+
+    if total_weight <= 0. {
+        return 0.;
+    }
+    return misclassified_weight / total_weight;
+}
+
+/// Mean balanced error of the outputs as binary classifiers of their class against all other classes,
+/// a positive output meaning the class. `outputs` holds one vector per class.
+/// Only classes occurring in the labels are counted.
+pub fn fitness_one_vs_rest(outputs: &Vec<Vec<f32>>, labels: &Vec<usize>) -> f32 {
+    let classes: Vec<usize> = labels.iter().cloned().unique().collect();
+
+    let mut error = 0.;
+    for class in &classes {
+        let mut true_positives = 0.;
+        let mut positives = 0.;
+        let mut true_negatives = 0.;
+        let mut negatives = 0.;
+        outputs[*class].iter().zip(labels.iter()).for_each(|(output, label)| {
+            if label == class {
+                positives += 1.;
+                if *output > 0. {
+                    true_positives += 1.;
+                }
+            } else {
+                negatives += 1.;
+                if *output <= 0. || output.is_nan() {
+                    true_negatives += 1.;
+                }
+            }
+        });
+
+        let balanced_accuracy = match negatives > 0. {
+            true => (true_positives / positives + true_negatives / negatives) / 2.,
+            false => true_positives / positives,
+        };
+        error += 1. - balanced_accuracy;
+    }
+
+    return error / classes.len() as f32;
+}
+
+/// Cross-entropy of a sample whose probability for its class is below 1e-7, or whose outputs are not finite.
+pub const MAX_CROSS_ENTROPY: f64 = 16.11809565095832;
+
+/// Mean cross-entropy of the softmax of the outputs. `outputs` holds one vector per class.
+/// The loss of a sample is capped at `MAX_CROSS_ENTROPY`.
+pub fn fitness_cross_entropy(outputs: &Vec<Vec<f32>>, labels: &Vec<usize>) -> f32 {
+    let mut loss_sum: f64 = 0.;
+    for (sample, label) in labels.iter().enumerate() {
+        let max = outputs.iter().map(|output| output[sample] as f64).fold(f64::NEG_INFINITY, f64::max);
+        let log_sum_exp = outputs
+            .iter()
+            .map(|output| (output[sample] as f64 - max).exp())
+            .sum::<f64>()
+            .ln();
+        let loss = log_sum_exp - (outputs[*label][sample] as f64 - max);
+
+        loss_sum += match loss.is_finite() {
+            true => loss.clamp(0., MAX_CROSS_ENTROPY),
+            false => MAX_CROSS_ENTROPY,
+        };
+    }
+
+    return (loss_sum / labels.len() as f64) as f32;
+}
+
 /// Matthews correlation coefficient (MCC) / Phi coefficient
 pub fn fitness_categorical_binary(prediction: &Vec<usize>, labels: &Vec<usize>) -> f32 {
     let mut true_positive: f32 = 0.;
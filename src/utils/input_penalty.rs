@@ -0,0 +1,70 @@
+use serde::{Deserialize, Serialize};
+use crate::analysis::attribute_usage::AttributeLabels;
+use crate::vanilla_cgp::chromosome::Chromosome;
+
+/// Feature-selection term added to the training fitness: `weight` for every distinct input used by the active graph.
+/// Only the selection sees the penalized fitness; logs and summaries report the error and the penalty separately.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct InputPenalty {
+    /// penalty per counted input; 0 disables the term
+    pub weight: f32,
+    /// an original attribute and all its (noisy) copies count as one input
+    #[serde(default)]
+    pub count_copies_once: bool,
+}
+
+impl InputPenalty {
+    pub fn is_enabled(&self) -> bool {
+        return self.weight != 0.;
+    }
+}
+
+/// `InputPenalty` for the inputs of one dataset.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct InputPenaltyTerm {
+    weight: f32,
+    /// inputs with the same group are counted once
+    input_groups: Vec<usize>,
+}
+
+impl InputPenaltyTerm {
+    /// Groups the copies by the source attributes of `labels`; noise attributes are always counted on their own.
+    pub fn new(penalty: InputPenalty, labels: &AttributeLabels) -> Self {
+        let nbr_inputs = labels.nbr_inputs();
+        let input_groups: Vec<usize> = (0..nbr_inputs)
+            .map(|input_id| match (penalty.count_copies_once, labels.source(input_id)) {
+                (true, Some(source)) => source,
+                // sources are below nbr_inputs, so these groups are distinct from them
+                _ => nbr_inputs + input_id,
+            })
+            .collect();
+
+        Self {
+            weight: penalty.weight,
+            input_groups,
+        }
+    }
+
+    /// Distinct inputs of the active graph, with copies grouped if configured; expects up to date `active_nodes`.
+    pub fn nbr_counted_inputs(&self, chromosome: &Chromosome) -> usize {
+        let mut groups: Vec<usize> = chromosome.active_nodes
+            .iter()
+            .filter(|node_id| **node_id < self.input_groups.len())
+            .map(|input_id| self.input_groups[*input_id])
+            .collect();
+        groups.sort();
+        groups.dedup();
+        return groups.len();
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        return self.weight != 0.;
+    }
+
+    pub fn penalty(&self, chromosome: &Chromosome) -> f32 {
+        if !self.is_enabled() {
+            return 0.;
+        }
+        return self.weight * self.nbr_counted_inputs(chromosome) as f32;
+    }
+}
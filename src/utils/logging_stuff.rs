@@ -1,39 +1,77 @@
-use std::fs::File;
+use std::fs::{File, OpenOptions};
+use std::io;
 use std::io::prelude::*;
 use std::io::BufWriter;
 use std::path::PathBuf;
-use std::io::prelude::*;
 use flate2::Compression;
 use flate2::write;
-use std::error::Error;
 use std::ffi::OsStr;
 
 // #[cfg(feature = "vanilla")]
-// use crate::vanilla_cgp::chromosome::Chromosome;
+use crate::vanilla_cgp::chromosome::Chromosome;
 // #[cfg(feature = "dag")]
-use crate::dag::chromosome_dag::Chromosome;
+// use crate::dag::chromosome_dag::Chromosome;
+
 
+/// Buffered writer of a log file, compressed for `.gz` files.
+enum LogWriter {
+    Plain(BufWriter<File>),
+    Compressed(BufWriter<write::GzEncoder<File>>),
+}
+
+impl LogWriter {
+    fn writer(&mut self) -> &mut dyn Write {
+        return match self {
+            LogWriter::Plain(writer) => writer,
+            LogWriter::Compressed(writer) => writer,
+        };
+    }
 
-fn write_compr(path: &PathBuf) -> Box<dyn Write> {
-    let file = match File::create(path) {
-        Err(why) => panic!("couldn't open {}: {}", path.display(), why.description()),
+    /// Writes the buffer and, for `.gz`, the gzip trailer. Dropping the writer would do the same but ignore errors.
+    fn finish(self) -> io::Result<()> {
+        match self {
+            LogWriter::Plain(mut writer) => writer.flush()?,
+            LogWriter::Compressed(writer) => {
+                writer.into_inner().map_err(|e| e.into_error())?.finish()?.flush()?;
+            }
+        }
+        return Ok(());
+    }
+}
+
+/// Opens the file for writing and compresses the output if the file ends with `.gz`.
+/// Appending to a `.gz` file starts a new gzip member; `zcat` and `MultiGzDecoder` read all members.
+fn write_compr(path: &PathBuf, append: bool) -> LogWriter {
+    let file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .append(append)
+        .truncate(!append)
+        .open(path);
+    let file: File = match file {
+        Err(why) => panic!("couldn't open {}: {}", path.display(), why),
         Ok(file) => file,
     };
 
     if path.extension() == Some(OsStr::new("gz")) {
-        Box::new(BufWriter::with_capacity(
+        LogWriter::Compressed(BufWriter::with_capacity(
             128 * 1024,
             write::GzEncoder::new(file, Compression::default()),
         ))
     } else {
-        Box::new(BufWriter::with_capacity(128 * 1024, file))
+        LogWriter::Plain(BufWriter::with_capacity(128 * 1024, file))
     }
 }
 
 
+/// Writes one line per logged chromosome:
+/// `Iteration: <it>;Active_Nodes: [..];Genes: (function_id,connection0,connection1)-...`
+/// unused connections are written as -1.
 pub struct LoggingStuff {
+    path: PathBuf,
     // pub buffered_writer: BufWriter<File>,
-    pub buffered_compressed_writer: Box<dyn Write>,
+    /// None only while `checkpoint` reopens the file
+    buffered_compressed_writer: Option<LogWriter>,
 
 }
 
@@ -42,23 +80,39 @@ impl LoggingStuff {
     pub fn new(path: PathBuf) -> Self {
         // let file = File::create(path).unwrap();
         // let buffered_writer = BufWriter::with_capacity(100, file);
-        let writer = write_compr(&path);
+        let writer = write_compr(&path, false);
+        Self {
+            path,
+            buffered_compressed_writer: Some(writer),
+        }
+    }
+
+    /// Continues a log that was cut at `len` bytes by `checkpoint`. Everything written after it is dropped.
+    pub fn resume(path: PathBuf, len: u64) -> Self {
+        let file = OpenOptions::new()
+            .write(true)
+            .open(&path)
+            .expect("cannot open genome log");
+        file.set_len(len).expect("cannot truncate genome log");
+
+        let writer = write_compr(&path, true);
         Self {
-            buffered_compressed_writer: writer,
+            path,
+            buffered_compressed_writer: Some(writer),
         }
     }
 
-    pub fn execute(&mut self, it: usize, chromosome: Chromosome) {
+    pub fn execute(&mut self, it: usize, chromosome: &Chromosome) {
         let mut write_string = String::new();
 
         write_string.push_str("Iteration: ");
         write_string.push_str(format!("{};", it).as_str());
 
         write_string.push_str("Active_Nodes: ");
-        write_string.push_str(format!("{:?};", chromosome.active_nodes.unwrap()).as_str());
+        write_string.push_str(format!("{:?};", chromosome.active_nodes).as_str());
 
         write_string.push_str("Genes: ");
-        for node in chromosome.nodes_grid {
+        for node in &chromosome.nodes_grid {
             let con1: i64;
             let con2: i64;
             if node.connection0 == usize::MAX {
@@ -76,13 +130,24 @@ impl LoggingStuff {
             write_string.push_str(format!("({},{},{})-", node.function_id, con1, con2).as_str());
         }
 
-        write_string.push_str("\n");
+        write_string.push('\n');
         // self.buffered_writer.write(write_string.as_ref());
-        self.buffered_compressed_writer.write_all(write_string.as_ref()).unwrap();
+        self.buffered_compressed_writer.as_mut().unwrap().writer().write_all(write_string.as_ref()).unwrap();
+    }
+
+    /// Completes everything written so far (for `.gz` the current gzip member) and
+    /// returns the length of the file, to be passed to `resume`.
+    pub fn checkpoint(&mut self) -> u64 {
+        self.buffered_compressed_writer.take().unwrap().finish()
+            .unwrap_or_else(|e| panic!("cannot write genome log {}: {}", self.path.display(), e));
+        let len = self.path.metadata().expect("cannot read genome log").len();
+        self.buffered_compressed_writer = Some(write_compr(&self.path, true));
+        return len;
     }
 
-    pub fn close_writer(&mut self) {
+    /// Finishes the log; for `.gz` this completes the gzip member.
+    pub fn close_writer(self) -> io::Result<()> {
         // self.buffered_writer.flush().unwrap();
-        self.buffered_compressed_writer.flush().unwrap();
+        return self.buffered_compressed_writer.unwrap().finish();
     }
 }
@@ -3,5 +3,16 @@ pub mod runner;
 pub mod node_type;
 pub mod cycle_checker;
 pub mod utility_funcs;
-// pub mod logging_stuff;
-pub mod symbolic_regression_functions;
\ No newline at end of file
+pub mod logging_stuff;
+pub mod symbolic_regression_functions;
+pub mod experiment;
+pub mod checkpoint;
+pub mod stopping_criteria;
+pub mod run_logger;
+pub mod model;
+pub mod mutation_strategy;
+pub mod connection_sampling;
+pub mod multiclass_decoding;
+pub mod class_costs;
+pub mod selection;
+pub mod input_penalty;
\ No newline at end of file
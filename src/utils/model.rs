@@ -0,0 +1,115 @@
+use std::fs;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use rand::Rng;
+use rand::distributions::{Distribution, Uniform};
+use serde::{Deserialize, Serialize};
+use statrs::distribution::Normal;
+use crate::analysis::attribute_usage::{AttributeKind, AttributeLabels};
+use crate::datasets::dataset_utils::{LabelEncoder, Scaler};
+use crate::utils::symbolic_regression_functions::FUNCTION_SET;
+use crate::vanilla_cgp::chromosome::Chromosome;
+
+/// A trained program together with everything needed to apply it to raw data:
+/// the genome and its `CgpParameters`, the function set it was evolved with,
+/// the standardization of the original attributes and the layout of the input nodes.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Model {
+    pub chromosome: Chromosome,
+    /// function names indexed by function id, checked against `FUNCTION_SET` when loading
+    pub function_set: Vec<String>,
+    pub scaler: Scaler,
+    /// which original attribute (or noise) feeds every input node
+    pub input_layout: AttributeLabels,
+    /// original class labels of the predicted class ids
+    #[serde(default)]
+    pub label_encoder: Option<LabelEncoder>,
+}
+
+impl Model {
+    pub fn new(chromosome: Chromosome,
+               scaler: Scaler,
+               input_layout: AttributeLabels,
+               label_encoder: Option<LabelEncoder>) -> Self {
+        Self {
+            chromosome,
+            function_set: FUNCTION_SET.iter().map(|name| name.to_string()).collect(),
+            scaler,
+            input_layout,
+            label_encoder,
+        }
+    }
+
+    pub fn path(save_path: &Path, run_id: usize) -> PathBuf {
+        return save_path.join(format!("run_{}_model.json", run_id));
+    }
+
+    pub fn save(&self, path: &Path) {
+        let mut writer = BufWriter::new(File::create(path).expect("cannot create model file"));
+        serde_json::to_writer(&mut writer, self).expect("cannot write model");
+        writer.flush().expect("cannot write model");
+    }
+
+    pub fn load(path: &Path) -> Self {
+        let reader = BufReader::new(File::open(path).expect("cannot open model file"));
+        let model: Model = serde_json::from_reader(reader)
+            .unwrap_or_else(|e| panic!("invalid model {}: {}", path.display(), e));
+
+        assert_eq!(model.function_set, FUNCTION_SET, "model was evolved with a different function set");
+        return model;
+    }
+
+    /// Predicted class of every sample. `samples` hold the raw original attributes, one row per sample.
+    /// They are standardized, and the redundant inputs are rebuilt as in `add_redundancies`:
+    /// copies repeat their attribute, noisy copies and noise attributes draw new noise from `rng`.
+    pub fn predict<R: Rng>(&self, samples: Vec<Vec<f32>>, rng: &mut R) -> Vec<usize> {
+        let samples = self.scaler.transform(samples);
+        let inputs = self.build_inputs(&samples, rng);
+        return self.chromosome.predict(&inputs);
+    }
+
+    fn build_inputs<R: Rng>(&self, samples: &Vec<Vec<f32>>, rng: &mut R) -> Vec<Vec<f32>> {
+        let between = Uniform::new(-0.1, 0.1);
+        let normal_distribution = Normal::new(0.0, 1.0).unwrap();
+
+        return samples
+            .iter()
+            .map(|sample| {
+                (0..self.input_layout.nbr_inputs())
+                    .map(|input_id| {
+                        match (self.input_layout.kind(input_id), self.input_layout.source(input_id)) {
+                            (AttributeKind::Noise, _) => rng.sample(normal_distribution) as f32,
+                            (AttributeKind::NoisyCopy, Some(source)) => {
+                                let noise: f32 = between.sample(rng);
+                                sample[source] + sample[source] * noise
+                            }
+                            (_, Some(source)) => sample[source],
+                            (kind, None) => panic!("{} input {} has no source attribute", kind, input_id),
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+    }
+}
+
+/// Reads comma separated attribute values, one sample per line. A header line is skipped.
+pub fn read_samples(path: &Path) -> Vec<Vec<f32>> {
+    let contents = fs::read_to_string(path)
+        .unwrap_or_else(|e| panic!("cannot read {}: {}", path.display(), e));
+
+    let mut samples: Vec<Vec<f32>> = vec![];
+    for (line_nbr, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let values: Result<Vec<f32>, _> = line.split(',').map(|val| val.trim().parse::<f32>()).collect();
+        match values {
+            Ok(values) => samples.push(values),
+            Err(_) if line_nbr == 0 => continue,
+            Err(e) => panic!("line {} of {}: {}", line_nbr + 1, path.display(), e),
+        }
+    }
+    return samples;
+}
@@ -0,0 +1,88 @@
+use serde::{Deserialize, Serialize};
+
+/// How the outputs of a multiclass program are turned into a class and a fitness.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MulticlassDecoding {
+    /// one output per class, the largest one wins; fitness is the balanced error
+    Argmax,
+    /// a single output, cut into class intervals by `ClassThresholds` learned on the training data;
+    /// fitness is the balanced error
+    Thresholds,
+    /// one output per class, each a binary program voting for its class with a positive value;
+    /// fitness is the mean balanced error of the binary programs.
+    /// The voter with the largest output wins, or the largest output if none votes
+    OneVsRest,
+    /// one output per class, turned into probabilities by softmax; fitness is the mean cross-entropy,
+    /// the class with the largest probability wins
+    Softmax,
+}
+
+impl Default for MulticlassDecoding {
+    fn default() -> Self {
+        return MulticlassDecoding::Argmax;
+    }
+}
+
+impl MulticlassDecoding {
+    pub fn nbr_outputs(&self, nbr_classes: usize) -> usize {
+        return match self {
+            MulticlassDecoding::Thresholds => 1,
+            _ => nbr_classes,
+        };
+    }
+}
+
+/// Decodes a single output into classes. The classes are ordered by their mean output on the
+/// training data, and consecutive classes are separated at the midpoint of their means.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ClassThresholds {
+    /// classes in ascending order of their mean output
+    pub class_order: Vec<usize>,
+    /// ascending; `thresholds[i]` separates `class_order[i]` from `class_order[i + 1]`
+    pub thresholds: Vec<f32>,
+}
+
+impl ClassThresholds {
+    /// Non-finite outputs are ignored. Classes without finite outputs are placed last with a mean above
+    /// all others, so that the thresholds stay finite and survive a round trip through JSON.
+    pub fn fit(outputs: &[f32], labels: &[usize], nbr_classes: usize) -> Self {
+        let mut sums = vec![0f64; nbr_classes];
+        let mut counts = vec![0usize; nbr_classes];
+        for (output, label) in outputs.iter().zip(labels.iter()) {
+            if output.is_finite() {
+                sums[*label] += *output as f64;
+                counts[*label] += 1;
+            }
+        }
+        let means: Vec<Option<f64>> = sums
+            .iter()
+            .zip(counts.iter())
+            .map(|(sum, count)| match count {
+                0 => None,
+                _ => Some(sum / *count as f64),
+            })
+            .collect();
+        let last = means.iter().flatten().fold(0f64, |max, mean| max.max(*mean)) + 1.;
+        let means: Vec<f64> = means.iter().map(|mean| mean.unwrap_or(last)).collect();
+
+        let mut class_order: Vec<usize> = (0..nbr_classes).collect();
+        class_order.sort_by(|a, b| means[*a].total_cmp(&means[*b]));
+
+        let thresholds: Vec<f32> = class_order
+            .windows(2)
+            .map(|pair| ((means[pair[0]] + means[pair[1]]) / 2.) as f32)
+            .collect();
+
+        Self {
+            class_order,
+            thresholds,
+        }
+    }
+
+    /// NaN outputs get the first class.
+    pub fn predict(&self, output: f32) -> usize {
+        let position = self.thresholds.iter().filter(|threshold| output >= **threshold).count();
+        return self.class_order[position];
+    }
+}
@@ -0,0 +1,213 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use statrs::distribution::Normal;
+use crate::vanilla_cgp::chromosome::Chromosome;
+
+/// How offspring are created from the parent.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MutationStrategy {
+    /// mutates random nodes until an active one was hit
+    SingleActive,
+    /// every gene is mutated with probability `rate`
+    Point {
+        rate: f32,
+        /// if no used gene of an active node changed, one of them is mutated in addition
+        #[serde(default)]
+        force_active: bool,
+    },
+    /// like `Point`, with separate probabilities for connection and function genes
+    Genewise {
+        connection_rate: f32,
+        function_rate: f32,
+        #[serde(default)]
+        force_active: bool,
+    },
+}
+
+impl Default for MutationStrategy {
+    fn default() -> Self {
+        return MutationStrategy::SingleActive;
+    }
+}
+
+impl MutationStrategy {
+    pub fn mutate<R: Rng>(&self, chromosome: &mut Chromosome, rng: &mut R) {
+        match *self {
+            MutationStrategy::SingleActive => chromosome.mutate_single(rng),
+            MutationStrategy::Point { rate, force_active } => {
+                chromosome.mutate_genewise(rate, rate, force_active, rng)
+            }
+            MutationStrategy::Genewise { connection_rate, function_rate, force_active } => {
+                chromosome.mutate_genewise(connection_rate, function_rate, force_active, rng)
+            }
+        }
+    }
+
+    /// The rate adapted by a `RateControl`: the rate of `Point`, the connection rate of `Genewise`.
+    /// None for `SingleActive`.
+    pub fn rate(&self) -> Option<f32> {
+        return match *self {
+            MutationStrategy::SingleActive => None,
+            MutationStrategy::Point { rate, .. } => Some(rate),
+            MutationStrategy::Genewise { connection_rate, .. } => Some(connection_rate),
+        };
+    }
+
+    /// The same strategy mutating with `rate`. The function rate of `Genewise` keeps its ratio to the connection rate.
+    pub fn with_rate(&self, new_rate: f32) -> Self {
+        return match *self {
+            MutationStrategy::SingleActive => MutationStrategy::SingleActive,
+            MutationStrategy::Point { force_active, .. } => MutationStrategy::Point {
+                rate: new_rate,
+                force_active,
+            },
+            MutationStrategy::Genewise { connection_rate, function_rate, force_active } => MutationStrategy::Genewise {
+                connection_rate: new_rate,
+                function_rate: match connection_rate > 0. {
+                    true => function_rate * new_rate / connection_rate,
+                    false => new_rate,
+                },
+                force_active,
+            },
+        };
+    }
+}
+
+/// How the mutation rate of `Point` and `Genewise` changes during a run.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RateControl {
+    /// the rate of the mutation strategy is used throughout
+    Fixed,
+    /// every individual carries its own rate; an offspring multiplies the rate of its parent by `exp(tau * N(0, 1))`
+    /// before being mutated with it, so the rate is selected together with the genome
+    SelfAdaptive {
+        tau: f32,
+        min_rate: f32,
+        max_rate: f32,
+    },
+    /// 1/5th success rule: every `window` iterations the rate is multiplied by `factor` if more than a fifth
+    /// of the offspring were better than their parent, and divided by it if fewer were
+    OneFifth {
+        window: usize,
+        factor: f32,
+        min_rate: f32,
+        max_rate: f32,
+    },
+    /// linear decay from the initial rate to `final_rate` within `iterations`, then constant
+    Linear {
+        final_rate: f32,
+        iterations: usize,
+    },
+    /// geometric decay from the initial rate to `final_rate` within `iterations`, then constant
+    Exponential {
+        final_rate: f32,
+        iterations: usize,
+    },
+}
+
+impl Default for RateControl {
+    fn default() -> Self {
+        return RateControl::Fixed;
+    }
+}
+
+/// Current mutation rates of a population under a `RateControl`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RateController {
+    control: RateControl,
+    initial_rate: f32,
+    /// rate of every individual; all equal unless the control is self-adaptive
+    rates: Vec<f32>,
+    /// offspring better than their parent and evaluated offspring in the current 1/5th window
+    successes: usize,
+    trials: usize,
+}
+
+impl RateController {
+    pub fn new(control: RateControl, strategy: &MutationStrategy, population_size: usize) -> Self {
+        let initial_rate = match (control, strategy.rate()) {
+            (RateControl::Fixed, rate) => rate.unwrap_or(0.),
+            (_, Some(rate)) => rate,
+            (_, None) => panic!("mutation rate control needs point or genewise mutation"),
+        };
+        if let RateControl::Exponential { final_rate, .. } = control {
+            assert!(initial_rate > 0. && final_rate > 0., "exponential decay needs positive mutation rates");
+        }
+
+        Self {
+            control,
+            initial_rate,
+            rates: vec![initial_rate; population_size],
+            successes: 0,
+            trials: 0,
+        }
+    }
+
+    /// Sets the rate of an offspring created from `parent_id` and returns it. None if the rate is fixed.
+    pub fn offspring_rate<R: Rng>(&mut self, parent_id: usize, offspring_id: usize, rng: &mut R) -> Option<f32> {
+        let rate = match self.control {
+            RateControl::Fixed => return None,
+            RateControl::SelfAdaptive { tau, min_rate, max_rate } => {
+                let normal_distribution = Normal::new(0.0, 1.0).unwrap();
+                let step = rng.sample(normal_distribution) as f32;
+                (self.rates[parent_id] * (tau * step).exp()).clamp(min_rate, max_rate)
+            }
+            _ => self.rates[parent_id],
+        };
+        self.rates[offspring_id] = rate;
+        return Some(rate);
+    }
+
+    /// Called after the offspring of `parent_id` were evaluated, with the number of completed iterations
+    /// including the current one.
+    pub fn update(&mut self, iteration: usize, parent_id: usize, fitness_vals: &Vec<f32>) {
+        let rate = match self.control {
+            RateControl::Fixed | RateControl::SelfAdaptive { .. } => return,
+            RateControl::OneFifth { window, factor, min_rate, max_rate } => {
+                let parent_fitness = fitness_vals[parent_id];
+                self.successes += fitness_vals.iter().filter(|fitness| **fitness < parent_fitness).count();
+                self.trials += fitness_vals.len() - 1;
+                if window == 0 || !iteration.is_multiple_of(window) {
+                    return;
+                }
+
+                let success_ratio = self.successes as f32 / self.trials.max(1) as f32;
+                self.successes = 0;
+                self.trials = 0;
+                let rate = self.rates[parent_id];
+                if success_ratio > 0.2 {
+                    (rate * factor).clamp(min_rate, max_rate)
+                } else if success_ratio < 0.2 {
+                    (rate / factor).clamp(min_rate, max_rate)
+                } else {
+                    rate
+                }
+            }
+            RateControl::Linear { final_rate, iterations } => {
+                let progress = match iterations {
+                    0 => 1.,
+                    _ => (iteration as f32 / iterations as f32).min(1.),
+                };
+                self.initial_rate + (final_rate - self.initial_rate) * progress
+            }
+            RateControl::Exponential { final_rate, iterations } => {
+                let progress = match iterations {
+                    0 => 1.,
+                    _ => (iteration as f32 / iterations as f32).min(1.),
+                };
+                self.initial_rate * (final_rate / self.initial_rate).powf(progress)
+            }
+        };
+        self.rates.iter_mut().for_each(|individual_rate| *individual_rate = rate);
+    }
+
+    /// Rate of the individual, None if the control is fixed.
+    pub fn rate(&self, individual_id: usize) -> Option<f32> {
+        return match self.control {
+            RateControl::Fixed => None,
+            _ => self.rates.get(individual_id).cloned(),
+        };
+    }
+}
@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+use crate::analysis::attribute_usage::AttributeUsage;
+use crate::global_params::default_rows;
+use crate::utils::class_costs::ClassCosts;
+use crate::utils::input_penalty::InputPenalty;
+use crate::utils::connection_sampling::ConnectionSampling;
+use crate::utils::experiment::RunConfig;
+use crate::utils::multiclass_decoding::MulticlassDecoding;
+use crate::utils::mutation_strategy::{MutationStrategy, RateControl};
+use crate::utils::selection::{Objectives, Selection};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    Jsonl,
+    Csv,
+}
+
+impl LogFormat {
+    pub fn extension(&self) -> &'static str {
+        return match self {
+            LogFormat::Jsonl => "jsonl",
+            LogFormat::Csv => "csv",
+        };
+    }
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return match s {
+            "jsonl" => Ok(LogFormat::Jsonl),
+            "csv" => Ok(LogFormat::Csv),
+            _ => Err(format!("unknown log format: {}, expected jsonl or csv", s)),
+        };
+    }
+}
+
+/// One line of the run log, describing the parent at the given iteration.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct IterationRecord {
+    pub iteration: usize,
+    /// training error, without the input penalty
+    pub train_fitness: f32,
+    pub active_nodes: usize,
+    /// number of active input nodes that are redundant attributes
+    pub redundant_inputs: usize,
+    pub elapsed_seconds: f64,
+    /// mutation rate of the parent; None for single active mutation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mutation_rate: Option<f32>,
+    /// input penalty added to the fitness of the parent; None if the penalty is disabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_penalty: Option<f32>,
+}
+
+const CSV_HEADER: &str = "iteration,train_fitness,active_nodes,redundant_inputs,elapsed_seconds,mutation_rate,input_penalty";
+
+pub struct RunLogger {
+    path: PathBuf,
+    format: LogFormat,
+    writer: BufWriter<File>,
+}
+
+impl RunLogger {
+    pub fn path(save_path: &Path, run_id: usize, format: LogFormat) -> PathBuf {
+        return save_path.join(format!("run_{}_log.{}", run_id, format.extension()));
+    }
+
+    pub fn new(path: PathBuf, format: LogFormat) -> Self {
+        let mut writer = BufWriter::new(File::create(&path).expect("cannot create file"));
+        if format == LogFormat::Csv {
+            writeln!(writer, "{}", CSV_HEADER).expect("cannot write");
+        }
+
+        Self {
+            path,
+            format,
+            writer,
+        }
+    }
+
+    /// Continues a log that was `len` bytes long at the last checkpoint. Later records are dropped.
+    pub fn resume(path: PathBuf, format: LogFormat, len: u64) -> Self {
+        let file = OpenOptions::new()
+            .write(true)
+            .open(&path)
+            .expect("cannot open file");
+        file.set_len(len).expect("cannot truncate file");
+        let file = OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .expect("cannot open file");
+
+        Self {
+            path,
+            format,
+            writer: BufWriter::new(file),
+        }
+    }
+
+    pub fn log(&mut self, record: &IterationRecord) {
+        match self.format {
+            LogFormat::Jsonl => {
+                serde_json::to_writer(&mut self.writer, record).expect("cannot write");
+                writeln!(self.writer).expect("cannot write");
+            }
+            LogFormat::Csv => {
+                writeln!(self.writer, "{},{},{},{},{},{},{}",
+                         record.iteration,
+                         record.train_fitness,
+                         record.active_nodes,
+                         record.redundant_inputs,
+                         record.elapsed_seconds,
+                         record.mutation_rate.map(|rate| rate.to_string()).unwrap_or_default(),
+                         record.input_penalty.map(|penalty| penalty.to_string()).unwrap_or_default()).expect("cannot write");
+            }
+        }
+    }
+
+    /// Flushes the log and returns its length, to be passed to `resume`.
+    pub fn checkpoint(&mut self) -> u64 {
+        self.writer.flush().expect("cannot write");
+        return self.path.metadata().expect("cannot read log").len();
+    }
+
+    pub fn close_writer(&mut self) {
+        self.writer.flush().expect("cannot write");
+    }
+}
+
+/// Final result of one fold, written once to `run_N_summary.json`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RunSummary {
+    pub run_id: usize,
+    pub fold: usize,
+    pub seed: u64,
+    pub dataset: String,
+    pub data_bloat: f32,
+    pub redundancy_type: usize,
+    pub nbr_nodes: usize,
+    #[serde(default = "default_rows")]
+    pub rows: usize,
+    #[serde(default)]
+    pub levels_back: Option<usize>,
+    #[serde(default)]
+    pub mutation: MutationStrategy,
+    #[serde(default)]
+    pub rate_control: RateControl,
+    #[serde(default)]
+    pub connection_sampling: ConnectionSampling,
+    #[serde(default)]
+    pub multiclass_decoding: MulticlassDecoding,
+    /// costs of the fitness, indexed by class id; None for the default fitness
+    #[serde(default)]
+    pub class_costs: Option<ClassCosts>,
+    #[serde(default)]
+    pub selection: Selection,
+    #[serde(default)]
+    pub input_penalty: InputPenalty,
+    pub iterations: usize,
+    /// training error of the final parent, without the input penalty
+    pub fitness_train: f32,
+    /// input penalty of the final parent; None if the penalty is disabled
+    #[serde(default)]
+    pub input_penalty_train: Option<f32>,
+    pub fitness_eval: f32,
+    pub stop_reason: String,
+    pub elapsed_seconds: f64,
+    pub active_nodes: Vec<usize>,
+    /// simplified symbolic expression of every output of the final parent
+    pub expressions: Vec<String>,
+    /// original label of every class id; empty for regression
+    #[serde(default)]
+    pub classes: Vec<String>,
+    /// kinds of the attributes used by the final parent
+    pub attribute_usage: AttributeUsage,
+    /// original attribute -> input nodes holding its redundant versions, see `add_redundancies`
+    pub redundancy_mapping: HashMap<usize, Vec<usize>>,
+    /// configuration of the run, to tell whether an existing summary belongs to it; None for older summaries
+    #[serde(default)]
+    pub config: Option<RunConfig>,
+}
+
+impl RunSummary {
+    pub fn path(save_path: &Path, run_id: usize) -> PathBuf {
+        return save_path.join(format!("run_{}_summary.json", run_id));
+    }
+
+    pub fn save(&self, path: &Path) {
+        let mut writer = BufWriter::new(File::create(path).expect("cannot create file"));
+        serde_json::to_writer_pretty(&mut writer, self).expect("cannot write");
+        writer.flush().expect("cannot write");
+    }
+
+    pub fn load(path: &Path) -> Self {
+        let reader = BufReader::new(File::open(path).expect("cannot open file"));
+        return serde_json::from_reader(reader)
+            .unwrap_or_else(|e| panic!("invalid summary {}: {}", path.display(), e));
+    }
+}
+
+/// One solution of the Pareto front of a multi-objective run.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ParetoSolution {
+    pub objectives: Objectives,
+    /// test fitness, computed after the run for analysis only: the solutions were not chosen on it,
+    /// and picking one of them by it would overfit the test data. None without test data
+    pub post_hoc_fitness_eval: Option<f32>,
+    /// simplified symbolic expression of every output
+    pub expressions: Vec<String>,
+    pub active_nodes: Vec<usize>,
+}
+
+/// Final Pareto front of a fold with `Selection::Nsga2`, written to `run_N_pareto_front.json`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ParetoFront {
+    pub solutions: Vec<ParetoSolution>,
+}
+
+impl ParetoFront {
+    pub fn path(save_path: &Path, run_id: usize) -> PathBuf {
+        return save_path.join(format!("run_{}_pareto_front.json", run_id));
+    }
+
+    pub fn save(&self, path: &Path) {
+        let mut writer = BufWriter::new(File::create(path).expect("cannot create file"));
+        serde_json::to_writer_pretty(&mut writer, self).expect("cannot write");
+        writer.flush().expect("cannot write");
+    }
+}
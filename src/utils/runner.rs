@@ -1,6 +1,13 @@
 use std::fmt::{Display, Formatter};
 use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 use crate::global_params::CgpParameters as g_params;
+use crate::utils::input_penalty::InputPenaltyTerm;
+use crate::utils::mutation_strategy::{MutationStrategy, RateControl, RateController};
+use crate::utils::selection::{crowded_comparison, non_dominated_sort, nsga2_survivors, rank_and_crowding, Objectives, Selection};
 use crate::utils::utility_funcs;
 
 // #[cfg(feature = "vanilla")]
@@ -11,16 +18,49 @@ use crate::vanilla_cgp::chromosome::Chromosome;
 // use crate::reorder::chromosome_reorder_equidistant::Chromosome;
 
 
+/// Active input nodes of the parent at the given iteration.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InputUsageSnapshot {
+    pub iteration: usize,
+    pub active_inputs: Vec<usize>,
+}
+
+/// The datasets are not serialized; they are attached again with `attach_data` after deserializing.
+#[derive(Serialize, Deserialize)]
 pub struct Runner {
     params: g_params,
+    #[serde(skip)]
     data: Vec<Vec<f32>>,
+    #[serde(skip)]
     label: Vec<usize>,
+    #[serde(skip)]
     eval_data: Option<Vec<Vec<f32>>>,
+    #[serde(skip)]
     eval_label: Option<Vec<usize>>,
     population: Vec<Chromosome>,
     best_fitness: f32,
+    /// fitness used by the selection: the error plus the input penalty
     fitness_vals: Vec<f32>,
+    /// error without the input penalty
+    #[serde(default)]
+    raw_fitness_vals: Vec<f32>,
     parent_id: usize,
+    iteration: usize,
+    nbr_fitness_evaluations: usize,
+    /// iteration in which the best fitness improved the last time
+    last_improvement: usize,
+    /// record the active inputs of the parent every n iterations; 0 disables the recording
+    input_usage_interval: usize,
+    input_usage_history: Vec<InputUsageSnapshot>,
+    #[serde(default)]
+    mutation_strategy: MutationStrategy,
+    #[serde(default)]
+    rate_controller: RateController,
+    #[serde(default)]
+    selection: Selection,
+    #[serde(default)]
+    input_penalty: InputPenaltyTerm,
+    rng: ChaCha8Rng,
 }
 
 impl Display for Runner {
@@ -35,7 +75,9 @@ impl Runner {
                data: Vec<Vec<f32>>,
                label: Vec<usize>,
                mut eval_data: Option<Vec<Vec<f32>>>,
-               eval_label: Option<Vec<usize>>) -> Self {
+               eval_label: Option<Vec<usize>>,
+               seed: u64) -> Self {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
         let mut chromosomes: Vec<Chromosome> = Vec::with_capacity(params.mu + params.lambda);
         let mut fitness_vals: Vec<f32> = Vec::with_capacity(params.mu + params.lambda);
 
@@ -46,8 +88,8 @@ impl Runner {
         }
 
         for _ in 0..(params.mu + params.lambda) {
-            let mut chromosome = Chromosome::new(params.clone());
-            let fitness = chromosome.evaluate(&data, &label);
+            let mut chromosome = Chromosome::new(params.clone(), &mut rng);
+            let fitness = chromosome.evaluate_incremental(&data, &label);
             fitness_vals.push(fitness);
 
             chromosomes.push(chromosome);
@@ -55,6 +97,7 @@ impl Runner {
 
         let best_fitness = utility_funcs::get_min(&fitness_vals);
         let parent_id = utility_funcs::get_argmin(&fitness_vals);
+        let nbr_fitness_evaluations = fitness_vals.len();
 
         Self {
             params,
@@ -64,17 +107,137 @@ impl Runner {
             eval_label,
             population: chromosomes,
             best_fitness,
+            raw_fitness_vals: fitness_vals.clone(),
             fitness_vals,
             parent_id,
+            iteration: 0,
+            nbr_fitness_evaluations,
+            last_improvement: 0,
+            input_usage_interval: 0,
+            input_usage_history: vec![],
+            mutation_strategy: MutationStrategy::default(),
+            rate_controller: RateController::default(),
+            selection: Selection::default(),
+            input_penalty: InputPenaltyTerm::default(),
+            rng,
+        }
+    }
+
+    /// Sets the datasets of a deserialized runner. Expects the same (untransposed) data as `new`.
+    pub fn attach_data(&mut self,
+                       data: Vec<Vec<f32>>,
+                       label: Vec<usize>,
+                       eval_data: Option<Vec<Vec<f32>>>,
+                       eval_label: Option<Vec<usize>>) {
+        self.data = utility_funcs::transpose(data);
+        self.label = label;
+        self.eval_data = match eval_data {
+            None => None,
+            Some(v) => Some(utility_funcs::transpose(v)),
+        };
+        self.eval_label = eval_label;
+
+        // written before the input penalty existed, so the fitness is the error
+        if self.raw_fitness_vals.is_empty() {
+            self.raw_fitness_vals = self.fitness_vals.clone();
         }
     }
 
     pub fn learn_step(&mut self) {
-        self.mutate_chromosomes();
+        match self.selection {
+            Selection::Nsga2 { .. } => self.nsga2_step(),
+            Selection::Fitness | Selection::Parsimony => {
+                self.mutate_chromosomes();
+
+                self.eval_chromosomes();
+
+                self.rate_controller.update(self.iteration + 1, self.parent_id, &self.fitness_vals);
+
+                self.new_parent_by_neutral_search();
+            }
+        }
 
-        self.eval_chromosomes();
+        self.iteration += 1;
 
-        self.new_parent_by_neutral_search();
+        if self.input_usage_interval > 0 && self.iteration.is_multiple_of(self.input_usage_interval) {
+            self.record_input_usage();
+        }
+    }
+
+    /// Records the active inputs of the parent every `interval` iterations, starting with the current one.
+    pub fn track_input_usage(&mut self, interval: usize) {
+        self.input_usage_interval = interval;
+        if interval > 0 && self.input_usage_history.is_empty() {
+            self.record_input_usage();
+        }
+    }
+
+    pub fn set_mutation_strategy(&mut self, mutation_strategy: MutationStrategy) {
+        self.mutation_strategy = mutation_strategy;
+    }
+
+    /// Adapts the rate of the mutation strategy; call after `set_mutation_strategy`.
+    pub fn set_rate_control(&mut self, rate_control: RateControl) {
+        self.rate_controller = RateController::new(rate_control,
+                                                   &self.mutation_strategy,
+                                                   self.params.mu + self.params.lambda);
+    }
+
+    /// Adds the penalty to the fitness of the current population; call before the first `learn_step`.
+    pub fn set_input_penalty(&mut self, input_penalty: InputPenaltyTerm) {
+        self.input_penalty = input_penalty;
+
+        for i in 0..self.population.len() {
+            self.fitness_vals[i] = self.raw_fitness_vals[i] + self.input_penalty.penalty(&self.population[i]);
+        }
+        self.best_fitness = utility_funcs::get_min(&self.fitness_vals);
+        self.parent_id = utility_funcs::get_argmin(&self.fitness_vals);
+    }
+
+    /// For `Selection::Nsga2`, the population is filled up with new random chromosomes or
+    /// truncated to the best ones; call before the first `learn_step`.
+    pub fn set_selection(&mut self, selection: Selection) {
+        self.selection = selection;
+
+        if let Selection::Nsga2 { population_size } = selection {
+            assert!(population_size >= 2, "the NSGA-II population needs at least two chromosomes");
+
+            while self.population.len() < population_size {
+                let mut chromosome = Chromosome::new(self.params.clone(), &mut self.rng);
+                let error = chromosome.evaluate_incremental(&self.data, &self.label);
+                self.raw_fitness_vals.push(error);
+                self.fitness_vals.push(error + self.input_penalty.penalty(&chromosome));
+                self.population.push(chromosome);
+                self.nbr_fitness_evaluations += 1;
+            }
+            self.survive(population_size);
+            self.best_fitness = utility_funcs::get_min(&self.fitness_vals);
+        }
+    }
+
+    /// Mutation rate of the parent, None for single active mutation.
+    pub fn get_mutation_rate(&self) -> Option<f32> {
+        return self.rate_controller
+            .rate(self.parent_id)
+            .or(self.mutation_strategy.rate());
+    }
+
+    fn record_input_usage(&mut self) {
+        let parent = &self.population[self.parent_id];
+        let active_inputs: Vec<usize> = parent.active_nodes
+            .iter()
+            .filter(|node_id| **node_id < self.params.nbr_inputs)
+            .cloned()
+            .collect();
+
+        self.input_usage_history.push(InputUsageSnapshot {
+            iteration: self.iteration,
+            active_inputs,
+        });
+    }
+
+    pub fn get_input_usage_history(&self) -> &Vec<InputUsageSnapshot> {
+        return &self.input_usage_history;
     }
 
     fn new_parent_by_neutral_search(&mut self) {
@@ -85,12 +248,113 @@ impl Runner {
         if min_keys.len() == 1 {
             self.parent_id = min_keys[0];
         } else {
-            if min_keys.contains(&self.parent_id) {
+            if self.selection == Selection::Parsimony {
+                // lexicographic parsimony: only the smallest of the best chromosomes
+                let min_size = min_keys
+                    .iter()
+                    .map(|id| self.population[*id].nbr_active_computational_nodes())
+                    .min()
+                    .unwrap();
+                min_keys.retain(|id| self.population[*id].nbr_active_computational_nodes() == min_size);
+            }
+            if min_keys.len() > 1 && min_keys.contains(&self.parent_id) {
                 let index = min_keys.iter().position(|x| *x == self.parent_id).unwrap();
                 min_keys.remove(index);
             }
-            self.parent_id = *min_keys.choose(&mut rand::thread_rng()).unwrap();
+            self.parent_id = *min_keys.choose(&mut self.rng).unwrap();
+        }
+    }
+
+    fn objectives(&self) -> Vec<Objectives> {
+        // the inputs are an objective of their own, so the error is not penalized
+        return self.population
+            .iter()
+            .zip(self.raw_fitness_vals.iter())
+            .map(|(chromosome, error)| Objectives::of(chromosome, *error))
+            .collect();
+    }
+
+    /// One generation of NSGA-II: binary tournaments on the crowded comparison choose the chromosomes to mutate,
+    /// then parents and offspring are truncated to the population size.
+    fn nsga2_step(&mut self) {
+        let population_size = self.population.len();
+        let rank_and_crowding = rank_and_crowding(&self.objectives());
+
+        let mut offspring: Vec<Chromosome> = Vec::with_capacity(population_size);
+        for _ in 0..population_size {
+            let a = self.rng.gen_range(0..population_size);
+            let b = self.rng.gen_range(0..population_size);
+            let winner = match crowded_comparison(&rank_and_crowding[a], &rank_and_crowding[b]) {
+                std::cmp::Ordering::Greater => b,
+                _ => a,
+            };
+
+            let mut child = self.population[winner].clone();
+            self.mutation_strategy.mutate(&mut child, &mut self.rng);
+            offspring.push(child);
+        }
+
+        let data = &self.data;
+        let label = &self.label;
+        let offspring_errors: Vec<f32> = offspring
+            .par_iter_mut()
+            .map(|child| child.evaluate_incremental(data, label))
+            .collect();
+        self.nbr_fitness_evaluations += population_size;
+
+        for (child, error) in offspring.iter().zip(offspring_errors.iter()) {
+            self.fitness_vals.push(error + self.input_penalty.penalty(child));
+        }
+        self.population.extend(offspring);
+        self.raw_fitness_vals.extend(offspring_errors);
+        self.survive(population_size);
+
+        let best_fitness = utility_funcs::get_min(&self.fitness_vals);
+        if best_fitness < self.best_fitness {
+            // the iteration counter is increased after the step
+            self.last_improvement = self.iteration + 1;
+        }
+        self.best_fitness = best_fitness;
+    }
+
+    /// Keeps the `n` best chromosomes by non-dominated rank and crowding distance.
+    /// The parent becomes the one with the lowest error, the smallest on ties.
+    fn survive(&mut self, n: usize) {
+        let survivors = nsga2_survivors(&self.objectives(), n);
+
+        let mut population: Vec<Option<Chromosome>> = self.population.drain(..).map(Some).collect();
+        self.population = survivors.iter().map(|i| population[*i].take().unwrap()).collect();
+        self.fitness_vals = survivors.iter().map(|i| self.fitness_vals[*i]).collect();
+        self.raw_fitness_vals = survivors.iter().map(|i| self.raw_fitness_vals[*i]).collect();
+
+        self.parent_id = (0..self.population.len())
+            .min_by(|a, b| self.fitness_vals[*a]
+                .total_cmp(&self.fitness_vals[*b])
+                .then(self.population[*a].nbr_active_computational_nodes()
+                    .cmp(&self.population[*b].nbr_active_computational_nodes())))
+            .unwrap();
+    }
+
+    /// Chromosomes of the current population that are not dominated by any other, with their objectives,
+    /// sorted by error. Of solutions with the same objectives only one is kept.
+    pub fn get_pareto_front(&self) -> Vec<(Chromosome, Objectives)> {
+        let objectives = self.objectives();
+        let mut front: Vec<(Chromosome, Objectives)> = vec![];
+        for i in &non_dominated_sort(&objectives)[0] {
+            if front.iter().all(|(_, existing)| *existing != objectives[*i]) {
+                front.push((self.population[*i].clone(), objectives[*i]));
+            }
         }
+        front.sort_by(|(_, a), (_, b)| a.error.total_cmp(&b.error).then(a.active_nodes.cmp(&b.active_nodes)));
+        return front;
+    }
+
+    /// Fitness of a chromosome on the test data, None without test data.
+    /// Not meant for choosing among chromosomes, see `get_test_fitness`.
+    pub fn get_test_fitness_of(&self, chromosome: &Chromosome) -> Option<f32> {
+        let eval_data = self.eval_data.as_ref()?;
+        let mut chromosome = chromosome.clone();
+        return Some(chromosome.evaluate(eval_data, self.eval_label.as_ref().unwrap()));
     }
 
     fn mutate_chromosomes(&mut self) {
@@ -101,49 +365,97 @@ impl Runner {
             }
             self.population[i] = self.population[self.parent_id].clone();
 
-            self.population[i].mutate_single();
+            let strategy = match self.rate_controller.offspring_rate(self.parent_id, i, &mut self.rng) {
+                Some(rate) => self.mutation_strategy.with_rate(rate),
+                None => self.mutation_strategy,
+            };
+            strategy.mutate(&mut self.population[i], &mut self.rng);
 
         }
     }
 
     fn eval_chromosomes(&mut self) {
-        for i in 0..(self.params.mu + self.params.lambda) {
-            if i != self.parent_id {
-                let fitness: f32 = self.population[i].evaluate(&self.data, &self.label);
+        let parent_id = self.parent_id;
+        let data = &self.data;
+        let label = &self.label;
+        let input_penalty = &self.input_penalty;
 
-                self.fitness_vals[i] = fitness;
-            }
-        }
+        // evaluation does not draw random numbers, so evaluating in parallel
+        // gives the same results as evaluating sequentially
+        self.population
+            .par_iter_mut()
+            .zip(self.fitness_vals.par_iter_mut())
+            .zip(self.raw_fitness_vals.par_iter_mut())
+            .enumerate()
+            .filter(|(i, _)| *i != parent_id)
+            .for_each(|(_, ((individual, fitness), error))| {
+                // offspring are clones of the parent, so only the mutated part of the graph is recomputed
+                *error = individual.evaluate_incremental(data, label);
+                *fitness = *error + input_penalty.penalty(individual);
+            });
+
+        self.nbr_fitness_evaluations += self.params.mu + self.params.lambda - 1;
 
         let best_fitness = utility_funcs::get_min(&self.fitness_vals);
+        if best_fitness < self.best_fitness {
+            // eval_chromosomes is called before the iteration counter is increased
+            self.last_improvement = self.iteration + 1;
+        }
 
         self.best_fitness = best_fitness;
     }
 
-    pub fn get_test_fitness(&mut self) -> f32 {
-        let mut best_fitness = f32::MAX;
-
-        if self.eval_data.is_none() {
-            return best_fitness
-        }
+    /// Fitness of the parent on the test data, f32::MAX without test data.
+    /// Only the parent is scored, so the test data plays no part in choosing the model.
+    pub fn get_test_fitness(&self) -> f32 {
+        return self.get_test_fitness_of(&self.population[self.parent_id]).unwrap_or(f32::MAX);
+    }
 
-        for individual in &mut self.population {
-            let fitness = individual.evaluate(&self.eval_data.as_ref().unwrap(), &self.eval_label.as_ref().unwrap());
+    pub fn get_iteration(&self) -> usize {
+        return self.iteration;
+    }
 
-            if fitness < best_fitness {
-                best_fitness = fitness;
-            }
-        }
-        return best_fitness;
+    pub fn get_nbr_fitness_evaluations(&self) -> usize {
+        return self.nbr_fitness_evaluations;
+    }
 
+    pub fn get_iterations_without_improvement(&self) -> usize {
+        return self.iteration - self.last_improvement;
     }
 
+    /// Fitness of the parent as seen by the selection, including the input penalty.
     pub fn get_best_fitness(&self) -> f32 {
         return self.best_fitness;
     }
 
+    /// Training error of the parent, without the input penalty.
+    pub fn get_parent_error(&self) -> f32 {
+        return self.raw_fitness_vals[self.parent_id];
+    }
+
+    /// Input penalty of the parent, None if the penalty is disabled.
+    pub fn get_parent_input_penalty(&self) -> Option<f32> {
+        if !self.input_penalty.is_enabled() {
+            return None;
+        }
+        return Some(self.input_penalty.penalty(&self.population[self.parent_id]));
+    }
+
     pub fn get_parent(&self) -> Chromosome {
         return self.population[self.parent_id].clone();
     }
+
+    /// Predictions of the parent on the training data, with the training labels.
+    pub fn get_train_predictions(&self) -> (Vec<usize>, &Vec<usize>) {
+        let predictions = self.population[self.parent_id].predict_transposed(&self.data);
+        return (predictions, &self.label);
+    }
+
+    /// Predictions of the parent on the test data, with the test labels. None without test data.
+    pub fn get_test_predictions(&self) -> Option<(Vec<usize>, &Vec<usize>)> {
+        let eval_data = self.eval_data.as_ref()?;
+        let predictions = self.population[self.parent_id].predict_transposed(eval_data);
+        return Some((predictions, self.eval_label.as_ref().unwrap()));
+    }
 }
 
@@ -0,0 +1,153 @@
+use std::cmp::Ordering;
+use serde::{Deserialize, Serialize};
+use crate::vanilla_cgp::chromosome::Chromosome;
+
+/// How the parents of the next iteration are selected.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Selection {
+    /// (1 + λ) neutral search: the best chromosome becomes the parent, an offspring is preferred over the parent on ties
+    Fitness,
+    /// as `Fitness`, but ties are broken by the number of active computational nodes first (lexicographic parsimony)
+    Parsimony,
+    /// NSGA-II on the `Objectives`: `population_size` parents create as many offspring by binary tournament and
+    /// mutation, and the best of both by non-dominated rank and crowding distance survive.
+    /// The parent reported for logging is the chromosome with the lowest error
+    Nsga2 {
+        population_size: usize,
+    },
+}
+
+impl Default for Selection {
+    fn default() -> Self {
+        return Selection::Fitness;
+    }
+}
+
+/// Objectives of the multi-objective selection, all minimized.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Objectives {
+    /// fitness on the training data
+    pub error: f32,
+    /// active computational nodes
+    pub active_nodes: usize,
+    /// distinct input attributes used by the active graph
+    pub active_inputs: usize,
+}
+
+impl Objectives {
+    /// Expects up to date `active_nodes`, as left by `evaluate_incremental`.
+    pub fn of(chromosome: &Chromosome, fitness: f32) -> Self {
+        return Objectives {
+            error: fitness,
+            active_nodes: chromosome.nbr_active_computational_nodes(),
+            active_inputs: chromosome.nbr_active_inputs(),
+        };
+    }
+
+    /// NaN errors are worse than every other error.
+    fn values(&self) -> [f32; 3] {
+        let error = match self.error.is_nan() {
+            true => f32::INFINITY,
+            false => self.error,
+        };
+        return [error, self.active_nodes as f32, self.active_inputs as f32];
+    }
+
+    /// Not worse in any objective and better in at least one.
+    pub fn dominates(&self, other: &Objectives) -> bool {
+        let a = self.values();
+        let b = other.values();
+        return a.iter().zip(b.iter()).all(|(x, y)| x <= y) && a.iter().zip(b.iter()).any(|(x, y)| x < y);
+    }
+}
+
+/// Fronts of mutually non-dominated solutions, the Pareto front first (fast non-dominated sort of NSGA-II).
+pub fn non_dominated_sort(objectives: &[Objectives]) -> Vec<Vec<usize>> {
+    let n = objectives.len();
+    let mut dominated_by: Vec<Vec<usize>> = vec![vec![]; n];
+    let mut nbr_dominating: Vec<usize> = vec![0; n];
+
+    for i in 0..n {
+        for j in (i + 1)..n {
+            if objectives[i].dominates(&objectives[j]) {
+                dominated_by[i].push(j);
+                nbr_dominating[j] += 1;
+            } else if objectives[j].dominates(&objectives[i]) {
+                dominated_by[j].push(i);
+                nbr_dominating[i] += 1;
+            }
+        }
+    }
+
+    let mut fronts: Vec<Vec<usize>> = vec![];
+    let mut front: Vec<usize> = (0..n).filter(|i| nbr_dominating[*i] == 0).collect();
+    while !front.is_empty() {
+        let mut next_front: Vec<usize> = vec![];
+        for i in &front {
+            for j in &dominated_by[*i] {
+                nbr_dominating[*j] -= 1;
+                if nbr_dominating[*j] == 0 {
+                    next_front.push(*j);
+                }
+            }
+        }
+        fronts.push(front);
+        front = next_front;
+    }
+    return fronts;
+}
+
+/// Crowding distance of every solution of `front`, in the same order.
+/// The solutions at the boundary of an objective get infinity.
+pub fn crowding_distances(objectives: &[Objectives], front: &[usize]) -> Vec<f32> {
+    if front.len() <= 2 {
+        return vec![f32::INFINITY; front.len()];
+    }
+    let mut distances = vec![0f32; front.len()];
+
+    for objective in 0..3 {
+        let value = |position: usize| objectives[front[position]].values()[objective];
+
+        let mut order: Vec<usize> = (0..front.len()).collect();
+        order.sort_by(|a, b| value(*a).total_cmp(&value(*b)));
+
+        let (min, max) = (value(order[0]), value(order[order.len() - 1]));
+        distances[order[0]] = f32::INFINITY;
+        distances[order[order.len() - 1]] = f32::INFINITY;
+        if max <= min || !(max - min).is_finite() {
+            continue;
+        }
+        for k in 1..(order.len() - 1) {
+            distances[order[k]] += (value(order[k + 1]) - value(order[k - 1])) / (max - min);
+        }
+    }
+    return distances;
+}
+
+/// Non-dominated rank (0 for the Pareto front) and crowding distance within its front of every solution.
+pub fn rank_and_crowding(objectives: &[Objectives]) -> Vec<(usize, f32)> {
+    let mut result = vec![(0, 0.); objectives.len()];
+    for (rank, front) in non_dominated_sort(objectives).iter().enumerate() {
+        for (i, distance) in front.iter().zip(crowding_distances(objectives, front)) {
+            result[*i] = (rank, distance);
+        }
+    }
+    return result;
+}
+
+/// Crowded comparison of NSGA-II: lower rank first, then larger crowding distance.
+pub fn crowded_comparison(a: &(usize, f32), b: &(usize, f32)) -> Ordering {
+    return a.0.cmp(&b.0).then(b.1.total_cmp(&a.1));
+}
+
+/// Indices of the `n` solutions surviving the NSGA-II truncation, best first.
+pub fn nsga2_survivors(objectives: &[Objectives], n: usize) -> Vec<usize> {
+    let rank_and_crowding = rank_and_crowding(objectives);
+
+    let mut order: Vec<usize> = (0..objectives.len()).collect();
+    // stable, so ties keep the older solutions
+    order.sort_by(|a, b| crowded_comparison(&rank_and_crowding[*a], &rank_and_crowding[*b]));
+    order.truncate(n);
+    return order;
+}
@@ -0,0 +1,84 @@
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use crate::utils::runner::Runner;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StopReason {
+    MaxIterations,
+    MaxFitnessEvaluations,
+    TimeBudget,
+    TargetFitness,
+    Stagnation,
+}
+
+impl Display for StopReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StopReason::MaxIterations => write!(f, "max_iterations"),
+            StopReason::MaxFitnessEvaluations => write!(f, "max_fitness_evaluations"),
+            StopReason::TimeBudget => write!(f, "time_budget"),
+            StopReason::TargetFitness => write!(f, "target_fitness"),
+            StopReason::Stagnation => write!(f, "stagnation"),
+        }
+    }
+}
+
+/// All criteria that are set are combined: the run stops as soon as one of them is met.
+/// They are checked after every iteration, so a run does at least one iteration.
+/// The default stops after 100_000 iterations or once the fitness is at most 0.01.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StoppingCriteria {
+    pub max_iterations: Option<usize>,
+    pub max_fitness_evaluations: Option<usize>,
+    /// wall-clock budget in seconds
+    pub max_seconds: Option<f64>,
+    /// stop once the training error of the parent is at most this value, without the input penalty
+    pub target_fitness: Option<f32>,
+    /// stop if the best fitness did not improve for this many iterations
+    pub stagnation: Option<usize>,
+}
+
+impl Default for StoppingCriteria {
+    fn default() -> Self {
+        Self {
+            max_iterations: Some(100_000),
+            max_fitness_evaluations: None,
+            max_seconds: None,
+            target_fitness: Some(0.01),
+            stagnation: None,
+        }
+    }
+}
+
+impl StoppingCriteria {
+    pub fn check(&self, runner: &Runner, elapsed: Duration) -> Option<StopReason> {
+        if let Some(target_fitness) = self.target_fitness {
+            if runner.get_parent_error() <= target_fitness {
+                return Some(StopReason::TargetFitness);
+            }
+        }
+        if let Some(max_iterations) = self.max_iterations {
+            if runner.get_iteration() >= max_iterations {
+                return Some(StopReason::MaxIterations);
+            }
+        }
+        if let Some(max_fitness_evaluations) = self.max_fitness_evaluations {
+            if runner.get_nbr_fitness_evaluations() >= max_fitness_evaluations {
+                return Some(StopReason::MaxFitnessEvaluations);
+            }
+        }
+        if let Some(stagnation) = self.stagnation {
+            if runner.get_iterations_without_improvement() >= stagnation {
+                return Some(StopReason::Stagnation);
+            }
+        }
+        if let Some(max_seconds) = self.max_seconds {
+            if elapsed.as_secs_f64() >= max_seconds {
+                return Some(StopReason::TimeBudget);
+            }
+        }
+        return None;
+    }
+}
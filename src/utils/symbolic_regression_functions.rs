@@ -1,5 +1,8 @@
 use float_eq::float_eq;
 
+/// Names of the functions, indexed by `Node::function_id`.
+pub const FUNCTION_SET: [&str; 8] = ["add", "subtract", "mul", "div", "sin", "cos", "ln", "exp"];
+
 pub fn add(con1: &Vec<f32>, con2: &Vec<f32>) -> Vec<f32> {
     return con1.iter().zip(con2.iter()).map(|(a, b)| a + b).collect();
 }
@@ -1,5 +1,6 @@
 use std::collections::HashSet;
 use rand::distributions::{Distribution, Uniform};
+use rand::Rng;
 
 pub fn get_argmins_of_value(vecs: &Vec<f32>, res: &mut Vec<usize>, comp_value: f32) {
     vecs.iter()
@@ -42,16 +43,20 @@ pub fn vect_difference(v1: &Vec<usize>, v2: &Vec<usize>) -> Vec<usize> {
 }
 
 /// * upper_range is inclusive
-pub fn gen_random_number_for_node(excluded: usize, upper_range: usize) -> usize {
-    if upper_range <= 1 {
-        return 0;
+pub fn gen_random_number_for_node<R: Rng>(excluded: usize, upper_range: usize, rng: &mut R) -> usize {
+    return gen_random_number_in_range(excluded, 0, upper_range, rng);
+}
+
+/// Random number in `lower_range..upper_range` different from `excluded`, unless the range has only one number.
+pub fn gen_random_number_in_range<R: Rng>(excluded: usize, lower_range: usize, upper_range: usize, rng: &mut R) -> usize {
+    if upper_range <= lower_range + 1 {
+        return lower_range;
     }
 
-    let between = Uniform::from(0..=upper_range - 1);
-    let mut rng = rand::thread_rng();
+    let between = Uniform::from(lower_range..=upper_range - 1);
 
     loop {
-        let rand_nbr: usize = between.sample(&mut rng);
+        let rand_nbr: usize = between.sample(rng);
         if rand_nbr != excluded {
             return rand_nbr;
         }
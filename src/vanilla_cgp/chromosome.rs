@@ -1,21 +1,34 @@
 use std::collections::{HashMap, HashSet};
 use std::fmt::{Display, Formatter};
+use std::sync::Arc;
 use rand::distributions::{Distribution, Uniform};
 use rand::Rng;
 use crate::global_params::CgpParameters as g_params;
 use crate::vanilla_cgp::node::Node;
 use crate::utils::node_type::NodeType;
 use nohash_hasher::BuildNoHashHasher;
+use serde::{Deserialize, Serialize};
 use crate::datasets::fitness_metric_type::FitnessFuncType;
-use crate::utils::fitness_metrics::{fitness_categorical_multiclass, fitness_regression};
+use crate::utils::fitness_metrics::{fitness_categorical_multiclass, fitness_cross_entropy, fitness_one_vs_rest, fitness_regression};
+use crate::utils::multiclass_decoding::{ClassThresholds, MulticlassDecoding};
 use crate::utils::utility_funcs::{get_argmax, transpose};
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Chromosome {
     pub params: g_params,
     pub nodes_grid: Vec<Node>,
     pub output_node_ids: Vec<usize>,
     pub active_nodes: Vec<usize>,
+    /// outputs of the active nodes on the training data, computed by `evaluate_incremental`.
+    /// Offspring inherit them from the parent when cloned, so unchanged subgraphs are not recomputed.
+    /// Not serialized, the next evaluation recomputes them
+    #[serde(skip)]
+    pub cached_outputs: HashMap<usize, Arc<Vec<f32>>, BuildNoHashHasher<usize>>,
+    /// node ids mutated since the last call to `evaluate_incremental`
+    pub mutated_nodes: Vec<usize>,
+    /// decoding of the output for `MulticlassDecoding::Thresholds`, learned by `evaluate_incremental`
+    #[serde(default)]
+    pub class_thresholds: Option<ClassThresholds>,
 }
 
 impl Display for Chromosome {
@@ -31,7 +44,7 @@ impl Display for Chromosome {
 }
 
 impl Chromosome {
-    pub fn new(params: g_params) -> Self {
+    pub fn new<R: Rng>(params: g_params, rng: &mut R) -> Self {
         let mut nodes_grid: Vec<Node> = vec![];
         let mut output_node_ids: Vec<usize> = vec![];
         nodes_grid.reserve(params.nbr_inputs + params.graph_width + params.nbr_outputs);
@@ -40,17 +53,17 @@ impl Chromosome {
         // input nodes
         for position in 0..params.nbr_inputs {
             nodes_grid.push(Node::new(position,
-                                      params.nbr_inputs,
-                                      params.graph_width,
+                                      &params,
                                       NodeType::InputNode,
+                                      rng,
             ));
         }
         // computational nodes
         for position in params.nbr_inputs..(params.nbr_inputs + params.graph_width) {
             nodes_grid.push(Node::new(position,
-                                      params.nbr_inputs,
-                                      params.graph_width,
+                                      &params,
                                       NodeType::ComputationalNode,
+                                      rng,
             ));
         }
         // output nodes
@@ -58,9 +71,9 @@ impl Chromosome {
             ..
             (params.nbr_inputs + params.graph_width + params.nbr_outputs) {
             nodes_grid.push(Node::new(position,
-                                      params.nbr_inputs,
-                                      params.graph_width,
+                                      &params,
                                       NodeType::OutputNode,
+                                      rng,
             ));
         }
 
@@ -76,15 +89,50 @@ impl Chromosome {
             nodes_grid,
             output_node_ids,
             active_nodes: vec![],
+            cached_outputs: HashMap::default(),
+            mutated_nodes: vec![],
+            class_thresholds: None,
         }
     }
 
     pub fn evaluate(&mut self, inputs: &Vec<Vec<f32>>, labels: &Vec<usize>) -> f32 {
-        // let active_nodes = self.get_active_nodes_id();
-        // self.active_nodes = Some(self.get_active_nodes_id());
         self.get_active_nodes_id();
 
-        let mut outputs: HashMap<usize, Vec<f32>, BuildNoHashHasher<usize>> = HashMap::with_capacity_and_hasher(
+        let mut outputs = self.compute_outputs(inputs, &self.active_nodes);
+        return self.fitness_from_outputs(&mut outputs, labels);
+    }
+
+    /// Predicted class of every sample, decoded from the outputs as in `evaluate`.
+    /// `samples` are row-major: one row per sample, one value per input.
+    /// Does not change `active_nodes`.
+    pub fn predict(&self, samples: &Vec<Vec<f32>>) -> Vec<usize> {
+        return self.predict_transposed(&transpose(samples.clone()));
+    }
+
+    /// Same as `predict`, but takes the transposed inputs of `evaluate`.
+    pub fn predict_transposed(&self, inputs: &Vec<Vec<f32>>) -> Vec<usize> {
+        let mut outputs = self.compute_outputs(inputs, &self.active_node_ids());
+        return self.predictions_from_outputs(&mut outputs);
+    }
+
+    /// Raw output values, one row per sample and one value per output node.
+    /// `samples` are row-major as in `predict`.
+    pub fn predict_raw(&self, samples: &Vec<Vec<f32>>) -> Vec<Vec<f32>> {
+        let inputs = transpose(samples.clone());
+        let mut outputs = self.compute_outputs(&inputs, &self.active_node_ids());
+
+        let outs: Vec<Vec<f32>> = self.output_node_ids
+            .iter()
+            .map(|output_node_id| outputs.remove(output_node_id).unwrap().to_vec())
+            .collect();
+        return transpose(outs);
+    }
+
+    /// Outputs of the given active nodes on the transposed `inputs`.
+    fn compute_outputs(&self,
+                       inputs: &Vec<Vec<f32>>,
+                       active_nodes: &Vec<usize>) -> HashMap<usize, Arc<Vec<f32>>, BuildNoHashHasher<usize>> {
+        let mut outputs: HashMap<usize, Arc<Vec<f32>>, BuildNoHashHasher<usize>> = HashMap::with_capacity_and_hasher(
             self.params.nbr_inputs + self.params.graph_width + self.params.nbr_outputs,
             BuildNoHashHasher::default(),
         );
@@ -93,82 +141,207 @@ impl Chromosome {
         // as the inputs are transposed, the n-th element of the whole dataset is input
         // i.e. given a dataset with 3 datapoints per entry; and 5 entries.
         // then it will input the first datapoint of all 5 entries first. Then the second, etc.
+        for node_id in active_nodes {
+            let calculated_result = self.compute_node(*node_id, inputs, &outputs);
+            outputs.insert(*node_id, calculated_result);
+        }
+
+        return outputs;
+    }
+
+    /// Same as `evaluate`, but reuses the outputs of nodes whose subgraph has not changed since
+    /// the last call. A node is recomputed if it was mutated, was not active before,
+    /// or one of its inputs was recomputed. Must always be called with the same (training) data.
+    /// Learns the class thresholds for `MulticlassDecoding::Thresholds`.
+    pub fn evaluate_incremental(&mut self, inputs: &Vec<Vec<f32>>, labels: &Vec<usize>) -> f32 {
+        self.get_active_nodes_id();
+
+        let mut outputs: HashMap<usize, Arc<Vec<f32>>, BuildNoHashHasher<usize>> = HashMap::with_capacity_and_hasher(
+            self.active_nodes.len(),
+            BuildNoHashHasher::default(),
+        );
+        let mut recomputed: HashSet<usize, BuildNoHashHasher<usize>> = HashSet::with_capacity_and_hasher(
+            self.active_nodes.len(),
+            BuildNoHashHasher::default(),
+        );
+
+        // active nodes are sorted and connections only point backwards,
+        // so all inputs of a node are resolved before the node itself
         for node_id in &self.active_nodes {
-            // println!("{:?}", input_slice);
             let current_node: &Node = &self.nodes_grid[*node_id];
 
+            let mut dirty = self.mutated_nodes.contains(node_id);
             match current_node.node_type {
-                NodeType::InputNode => {
-                    outputs.insert(*node_id, inputs[*node_id].clone());
-                }
+                NodeType::InputNode => {}
                 NodeType::OutputNode => {
-                    let con1 = current_node.connection0;
-                    let prev_output1 = outputs.get(&con1).unwrap();
-                    outputs.insert(*node_id, prev_output1.clone());
+                    dirty |= recomputed.contains(&current_node.connection0);
                 }
                 NodeType::ComputationalNode => {
-                    let con1 = current_node.connection0;
-                    let prev_output1 = outputs.get(&con1).unwrap();
+                    dirty |= recomputed.contains(&current_node.connection0);
+                    if current_node.function_id <= 3 {
+                        dirty |= recomputed.contains(&current_node.connection1);
+                    }
+                }
+            }
+
+            if !dirty {
+                if let Some(cached) = self.cached_outputs.get(node_id) {
+                    outputs.insert(*node_id, Arc::clone(cached));
+                    continue;
+                }
+            }
 
-                    let calculated_result: Vec<f32>;
-                    if current_node.function_id <= 3 {  // case: two inputs needed
-                        let con2 = current_node.connection1;
-                        let prev_output2 = outputs.get(&con2).unwrap();
+            let calculated_result = self.compute_node(*node_id, inputs, &outputs);
+            outputs.insert(*node_id, calculated_result);
+            recomputed.insert(*node_id);
+        }
 
-                        calculated_result = current_node.execute(&prev_output1, Some(&prev_output2));
-                    } else {  // case: only one input needed
-                        calculated_result = current_node.execute(&prev_output1, None);
-                    }
-                    outputs.insert(*node_id, calculated_result);
+        self.cached_outputs = outputs.clone();
+        self.mutated_nodes.clear();
+
+        if self.uses_thresholds() {
+            // all classes of the label encoder, so classes missing in the training fold still get a threshold
+            let nbr_classes = labels.iter().max().map_or(0, |max| max + 1).max(self.params.nbr_classes);
+            let output = outputs.get(&self.output_node_ids[0]).unwrap();
+            self.class_thresholds = Some(ClassThresholds::fit(output, labels, nbr_classes));
+        }
+
+        return self.fitness_from_outputs(&mut outputs, labels);
+    }
+
+    /// Calculates the output of a single node. All its inputs must already be in `outputs`.
+    fn compute_node(&self,
+                    node_id: usize,
+                    inputs: &Vec<Vec<f32>>,
+                    outputs: &HashMap<usize, Arc<Vec<f32>>, BuildNoHashHasher<usize>>) -> Arc<Vec<f32>> {
+        let current_node: &Node = &self.nodes_grid[node_id];
+
+        match current_node.node_type {
+            NodeType::InputNode => {
+                return Arc::new(inputs[node_id].clone());
+            }
+            NodeType::OutputNode => {
+                let con1 = current_node.connection0;
+                return Arc::clone(outputs.get(&con1).unwrap());
+            }
+            NodeType::ComputationalNode => {
+                let con1 = current_node.connection0;
+                let prev_output1 = outputs.get(&con1).unwrap();
+
+                let calculated_result: Vec<f32>;
+                if current_node.function_id <= 3 {  // case: two inputs needed
+                    let con2 = current_node.connection1;
+                    let prev_output2 = outputs.get(&con2).unwrap();
+
+                    calculated_result = current_node.execute(prev_output1, Some(prev_output2));
+                } else {  // case: only one input needed
+                    calculated_result = current_node.execute(prev_output1, None);
                 }
+                return Arc::new(calculated_result);
             }
         }
+    }
+
+    fn uses_thresholds(&self) -> bool {
+        return self.params.fitness_func_type == FitnessFuncType::ClassificationMultiClass
+            && self.params.multiclass_decoding == MulticlassDecoding::Thresholds;
+    }
 
+    /// Values of all output nodes, removed from `outputs`.
+    fn output_values(&self,
+                     outputs: &mut HashMap<usize, Arc<Vec<f32>>, BuildNoHashHasher<usize>>) -> Vec<Vec<f32>> {
         let output_start_id = self.params.nbr_inputs + self.params.graph_width;
-        // let output_end_id = self.params.nbr_inputs + self.params.graph_width + self.params.nbr_outputs;
+        let output_end_id = self.params.nbr_inputs + self.params.graph_width + self.params.nbr_outputs;
 
-        // let mut outs: Vec<Vec<f32>> = Vec::with_capacity(output_end_id - output_start_id);
-        //
-        // for i in output_start_id..output_end_id {
-        //     outs.push(outputs.remove(&i).unwrap());
-        // }
+        let mut outs: Vec<Vec<f32>> = Vec::with_capacity(output_end_id - output_start_id);
+        for i in output_start_id..output_end_id {
+            outs.push(outputs.remove(&i).unwrap().to_vec());
+        }
+        return outs;
+    }
 
-        // let fitness = fitness_metrics::fitness_regression(&outs, &labels);
-        // let fitness = (self.fitness_func)(&outs, &labels);
-        let fitness = match self.params.fitness_func_type {
+    fn predictions_from_outputs(&self,
+                                outputs: &mut HashMap<usize, Arc<Vec<f32>>, BuildNoHashHasher<usize>>) -> Vec<usize> {
+        let output_start_id = self.params.nbr_inputs + self.params.graph_width;
+
+        let predictions = match self.params.fitness_func_type {
             FitnessFuncType::Regression => {
                 let outs = outputs.remove(&output_start_id).unwrap();
-                let outs = outs.iter().map(|x| *x as usize).collect();
-                fitness_regression(&outs, labels)
+                outs.iter().map(|x| *x as usize).collect()
+            }
+            FitnessFuncType::ClassificationMultiClass if self.uses_thresholds() => {
+                let thresholds = self.class_thresholds
+                    .as_ref()
+                    .expect("class thresholds are learned by evaluate_incremental");
+                let outs = outputs.remove(&output_start_id).unwrap();
+                outs.iter().map(|x| thresholds.predict(*x)).collect()
             }
             FitnessFuncType::ClassificationMultiClass => {
-                let output_end_id = self.params.nbr_inputs + self.params.graph_width + self.params.nbr_outputs;
-                let mut outs: Vec<Vec<f32>> = Vec::with_capacity(output_end_id - output_start_id);
-
-                for i in output_start_id..output_end_id {
-                    outs.push(outputs.remove(&i).unwrap());
-                }
-                let outs = transpose(outs);
-                let mut preds: Vec<usize> = Vec::with_capacity(labels.len());
+                // argmax, one-vs-rest and softmax all predict the largest output
+                let outs = transpose(self.output_values(outputs));
+                let mut preds: Vec<usize> = Vec::with_capacity(outs.len());
                 for res in outs {
                     preds.push(get_argmax(&res));
                 }
-
-                fitness_categorical_multiclass(&preds, &labels)
+                preds
             }
             FitnessFuncType::ClassificationBinary => {
                 let outs = outputs.remove(&output_start_id).unwrap();
-                let outs = outs.iter().map(|x| {
+                outs.iter().map(|x| {
                     if *x > 0. { 1 } else { 0 }
-                }).collect();
-                fitness_regression(&outs, labels)
+                }).collect()
+            }
+        };
+
+        return predictions;
+    }
+
+    fn fitness_from_outputs(&self,
+                            outputs: &mut HashMap<usize, Arc<Vec<f32>>, BuildNoHashHasher<usize>>,
+                            labels: &Vec<usize>) -> f32 {
+        if self.params.fitness_func_type == FitnessFuncType::ClassificationMultiClass {
+            match self.params.multiclass_decoding {
+                MulticlassDecoding::OneVsRest => return fitness_one_vs_rest(&self.output_values(outputs), labels),
+                MulticlassDecoding::Softmax => return fitness_cross_entropy(&self.output_values(outputs), labels),
+                MulticlassDecoding::Argmax | MulticlassDecoding::Thresholds => {}
             }
+        }
+
+        let predictions = self.predictions_from_outputs(outputs);
+
+        let fitness = match (self.params.fitness_func_type, &self.params.class_costs) {
+            (FitnessFuncType::Regression, _) => fitness_regression(&predictions, labels),
+            (_, Some(class_costs)) => class_costs.fitness(&predictions, labels),
+            (FitnessFuncType::ClassificationMultiClass, None) => fitness_categorical_multiclass(&predictions, &labels),
+            (FitnessFuncType::ClassificationBinary, None) => fitness_regression(&predictions, labels),
         };
 
         return fitness;
     }
 
+    /// Number of active computational nodes, from `active_nodes`.
+    pub fn nbr_active_computational_nodes(&self) -> usize {
+        let first_output_id = self.params.nbr_inputs + self.params.graph_width;
+        return self.active_nodes
+            .iter()
+            .filter(|node_id| **node_id >= self.params.nbr_inputs && **node_id < first_output_id)
+            .count();
+    }
+
+    /// Number of distinct input attributes used by the active graph, from `active_nodes`.
+    pub fn nbr_active_inputs(&self) -> usize {
+        return self.active_nodes
+            .iter()
+            .filter(|node_id| **node_id < self.params.nbr_inputs)
+            .count();
+    }
+
     pub fn get_active_nodes_id(&mut self) {
+        self.active_nodes = self.active_node_ids();
+    }
+
+    /// Ids of the nodes reachable from the outputs, sorted. Does not change `active_nodes`.
+    pub fn active_node_ids(&self) -> Vec<usize> {
         let mut active: HashSet<usize, BuildNoHashHasher<usize>> = HashSet::with_capacity_and_hasher(
             self.params.nbr_inputs + self.params.graph_width + self.params.nbr_outputs,
             BuildNoHashHasher::default(),
@@ -216,10 +389,10 @@ impl Chromosome {
         let mut active: Vec<usize> = active.into_iter().collect();
         active.sort_unstable();
 
-        self.active_nodes = active;
+        return active;
     }
 
-    pub fn mutate_single(&mut self) {
+    pub fn mutate_single<R: Rng>(&mut self, rng: &mut R) {
         let mut start_id = self.params.nbr_inputs;
         if start_id == 1 {
             // Serious edge case: if start_id == 1; then only the first node can be mutated.
@@ -231,11 +404,11 @@ impl Chromosome {
         let end_id = self.params.nbr_inputs + self.params.graph_width + self.params.nbr_outputs;
 
         let between = Uniform::from(start_id..=end_id - 1);
-        let mut rng = rand::thread_rng();
 
         loop {
-            let random_node_id = between.sample(&mut rng);
-            self.nodes_grid[random_node_id].mutate();
+            let random_node_id = between.sample(rng);
+            self.nodes_grid[random_node_id].mutate(rng);
+            self.mutated_nodes.push(random_node_id);
 
             if self.active_nodes.contains(&random_node_id) {
                 break;
@@ -243,7 +416,7 @@ impl Chromosome {
         }
     }
 
-    pub fn mutate_prob(&mut self, prob: f32) {
+    pub fn mutate_prob<R: Rng>(&mut self, prob: f32, rng: &mut R) {
         let mut start_id = self.params.nbr_inputs;
         if start_id == 1 {
             // Serious edge case: if start_id == 1; then only the first node can be mutated.
@@ -256,10 +429,60 @@ impl Chromosome {
 
 
         for node_id in start_id..end_id {
-            let random_prob: f32 = rand::thread_rng().gen::<f32>();
+            let random_prob: f32 = rng.gen::<f32>();
             if random_prob < prob {
-                self.nodes_grid[node_id].mutate();
+                self.nodes_grid[node_id].mutate(rng);
+                self.mutated_nodes.push(node_id);
             };
         }
     }
-}
+
+    /// Mutates every gene with its probability: `connection_rate` for connections,
+    /// `function_rate` for functions. Output nodes only have a connection gene.
+    /// With `force_active`, one used gene of an active node is mutated in addition
+    /// if none was hit, so the offspring differs from the parent in its phenotype.
+    /// Expects the active nodes to be up to date.
+    pub fn mutate_genewise<R: Rng>(&mut self, connection_rate: f32, function_rate: f32, force_active: bool, rng: &mut R) {
+        let mut start_id = self.params.nbr_inputs;
+        if start_id == 1 {
+            // same edge case as in `mutate_single`: the connections of the first node cannot change
+            start_id = 2;
+        }
+        let end_id = self.params.nbr_inputs + self.params.graph_width + self.params.nbr_outputs;
+
+        let mut active_changed = false;
+        for node_id in start_id..end_id {
+            let is_active = self.active_nodes.binary_search(&node_id).is_ok();
+            let mut mutated = false;
+
+            for gene in 0..self.nodes_grid[node_id].nbr_genes() {
+                let rate = if gene == 2 { function_rate } else { connection_rate };
+                if rng.gen::<f32>() < rate {
+                    active_changed |= is_active && self.nodes_grid[node_id].is_gene_used(gene);
+                    self.nodes_grid[node_id].mutate_gene(gene, rng);
+                    mutated = true;
+                }
+            }
+            if mutated {
+                self.mutated_nodes.push(node_id);
+            }
+        }
+
+        if force_active && !active_changed {
+            let active_genes: Vec<(usize, usize)> = self.active_nodes
+                .iter()
+                .filter(|node_id| **node_id >= start_id)
+                .flat_map(|node_id| {
+                    let node = &self.nodes_grid[*node_id];
+                    (0..node.nbr_genes())
+                        .filter(|gene| node.is_gene_used(*gene))
+                        .map(|gene| (*node_id, gene))
+                })
+                .collect();
+
+            let (node_id, gene) = active_genes[rng.gen_range(0..active_genes.len())];
+            self.nodes_grid[node_id].mutate_gene(gene, rng);
+            self.mutated_nodes.push(node_id);
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,212 @@
+use std::fmt::Write;
+use crate::datasets::fitness_metric_type::FitnessFuncType;
+use crate::utils::multiclass_decoding::MulticlassDecoding;
+use crate::utils::node_type::NodeType;
+use crate::vanilla_cgp::chromosome::Chromosome;
+
+/// Threshold of the protected division and logarithm, see `symbolic_regression_functions`.
+const PROTECTION_THRESHOLD: &str = "0.0001";
+
+#[derive(Clone, Copy, PartialEq)]
+enum Language {
+    Rust,
+    C,
+}
+
+impl Chromosome {
+    /// Self-contained Rust function `pub fn <function_name>(x: &[f32]) -> usize` computing the
+    /// prediction of the active graph for one sample, as `evaluate` does: argmax of the outputs for
+    /// multiclass classification (or the interval of the learned `class_thresholds` containing the output
+    /// for `MulticlassDecoding::Thresholds`), 1 if the output is positive for binary classification.
+    /// For regression, the function returns the raw output as `f32`.
+    pub fn to_rust_source(&self, function_name: &str) -> String {
+        return self.generate_source(function_name, Language::Rust);
+    }
+
+    /// C99 version of `to_rust_source`: `size_t <function_name>(const float *x)`.
+    /// Needs `math.h`, link with `-lm`.
+    pub fn to_c_source(&self, function_name: &str) -> String {
+        return self.generate_source(function_name, Language::C);
+    }
+
+    fn generate_source(&self, function_name: &str, language: Language) -> String {
+        let mut source = String::new();
+        let nbr_outputs = self.output_node_ids.len();
+
+        writeln!(source, "// Generated from an evolved CGP chromosome: {} inputs, {} outputs, {}.",
+                 self.params.nbr_inputs,
+                 nbr_outputs,
+                 match (self.params.fitness_func_type, self.params.multiclass_decoding) {
+                     (FitnessFuncType::Regression, _) => "regression",
+                     (FitnessFuncType::ClassificationMultiClass, MulticlassDecoding::Thresholds) => "multiclass classification (thresholds)",
+                     (FitnessFuncType::ClassificationMultiClass, _) => "multiclass classification (argmax)",
+                     (FitnessFuncType::ClassificationBinary, _) => "binary classification (output > 0)",
+                 }).unwrap();
+
+        let thresholds = match (self.params.fitness_func_type, self.params.multiclass_decoding) {
+            (FitnessFuncType::ClassificationMultiClass, MulticlassDecoding::Thresholds) => Some(
+                self.class_thresholds
+                    .as_ref()
+                    .expect("class thresholds are learned by evaluate_incremental")
+            ),
+            _ => None,
+        };
+
+        let return_type = match (language, self.params.fitness_func_type) {
+            (Language::Rust, FitnessFuncType::Regression) => "f32",
+            (Language::Rust, _) => "usize",
+            (Language::C, FitnessFuncType::Regression) => "float",
+            (Language::C, _) => "size_t",
+        };
+
+        match language {
+            Language::Rust => {
+                writeln!(source, "pub fn {}(x: &[f32]) -> {} {{", function_name, return_type).unwrap();
+            }
+            Language::C => {
+                writeln!(source, "#include <math.h>").unwrap();
+                writeln!(source, "#include <stddef.h>").unwrap();
+                writeln!(source, "#include <stdint.h>").unwrap();
+                writeln!(source, "#include <string.h>").unwrap();
+                writeln!(source).unwrap();
+                if self.params.fitness_func_type == FitnessFuncType::ClassificationMultiClass && thresholds.is_none() {
+                    // same order as Rust's f32::total_cmp, so ties and NaNs are resolved like `get_argmax`
+                    writeln!(source, "static int32_t {}_total_order_key(float value) {{", function_name).unwrap();
+                    writeln!(source, "    int32_t key;").unwrap();
+                    writeln!(source, "    memcpy(&key, &value, sizeof key);").unwrap();
+                    writeln!(source, "    return key ^ (int32_t)(((uint32_t)(key >> 31)) >> 1);").unwrap();
+                    writeln!(source, "}}").unwrap();
+                    writeln!(source).unwrap();
+                }
+                writeln!(source, "{} {}(const float *x) {{", return_type, function_name).unwrap();
+            }
+        }
+
+        for node_id in self.active_node_ids() {
+            let node = &self.nodes_grid[node_id];
+            if node.node_type != NodeType::ComputationalNode {
+                continue;
+            }
+
+            let a = self.operand_name(node.connection0);
+            let b = self.operand_name(node.connection1);
+            let expression = match (language, node.function_id) {
+                (_, 0) => format!("{} + {}", a, b),
+                (_, 1) => format!("{} - {}", a, b),
+                (_, 2) => format!("{} * {}", a, b),
+                (Language::Rust, 3) => format!("if {}.abs() <= {} {{ 1.0 }} else {{ {} / {} }}", b, PROTECTION_THRESHOLD, a, b),
+                (Language::Rust, 4) => format!("{}.sin()", a),
+                (Language::Rust, 5) => format!("{}.cos()", a),
+                (Language::Rust, 6) => format!("if {}.abs() <= {} {{ 1.0 }} else {{ {}.abs().ln() }}", a, PROTECTION_THRESHOLD, a),
+                (Language::Rust, 7) => format!("{}.exp()", a),
+                (Language::C, 3) => format!("fabsf({}) <= {}f ? 1.0f : {} / {}", b, PROTECTION_THRESHOLD, a, b),
+                (Language::C, 4) => format!("sinf({})", a),
+                (Language::C, 5) => format!("cosf({})", a),
+                (Language::C, 6) => format!("fabsf({}) <= {}f ? 1.0f : logf(fabsf({}))", a, PROTECTION_THRESHOLD, a),
+                (Language::C, 7) => format!("expf({})", a),
+                _ => panic!("wrong function id: {}", node.function_id),
+            };
+
+            match language {
+                Language::Rust => writeln!(source, "    let n{}: f32 = {};", node_id, expression).unwrap(),
+                Language::C => writeln!(source, "    const float n{} = {};", node_id, expression).unwrap(),
+            }
+        }
+
+        let outputs: Vec<String> = self.output_node_ids
+            .iter()
+            .map(|output_node_id| self.operand_name(self.nodes_grid[*output_node_id].connection0))
+            .collect();
+
+        if let Some(thresholds) = thresholds {
+            // NaN is below every threshold and gets the first class, as in `ClassThresholds::predict`
+            let threshold_values: Vec<String> = thresholds.thresholds
+                .iter()
+                .map(|threshold| float_literal(*threshold, language))
+                .collect();
+            let class_order: Vec<String> = thresholds.class_order.iter().map(|class| class.to_string()).collect();
+            let nbr_classes = class_order.len();
+            match language {
+                Language::Rust => {
+                    writeln!(source, "    let thresholds: [f32; {}] = [{}];", nbr_classes - 1, threshold_values.join(", ")).unwrap();
+                    writeln!(source, "    let class_order: [usize; {}] = [{}];", nbr_classes, class_order.join(", ")).unwrap();
+                    writeln!(source, "    let output: f32 = {};", outputs[0]).unwrap();
+                    writeln!(source, "    let position = thresholds.iter().filter(|threshold| output >= **threshold).count();").unwrap();
+                    writeln!(source, "    return class_order[position];").unwrap();
+                }
+                Language::C => {
+                    // C does not allow empty arrays, the sentinel is never read
+                    writeln!(source, "    const float thresholds[{}] = {{{}}};", nbr_classes, [threshold_values, vec!["0.0f".to_string()]].concat().join(", ")).unwrap();
+                    writeln!(source, "    const size_t class_order[{}] = {{{}}};", nbr_classes, class_order.join(", ")).unwrap();
+                    writeln!(source, "    const float output = {};", outputs[0]).unwrap();
+                    writeln!(source, "    size_t position = 0;").unwrap();
+                    writeln!(source, "    for (size_t i = 0; i < {}; i++) {{", nbr_classes - 1).unwrap();
+                    writeln!(source, "        if (output >= thresholds[i]) {{").unwrap();
+                    writeln!(source, "            position++;").unwrap();
+                    writeln!(source, "        }}").unwrap();
+                    writeln!(source, "    }}").unwrap();
+                    writeln!(source, "    return class_order[position];").unwrap();
+                }
+            }
+            writeln!(source, "}}").unwrap();
+
+            return source;
+        }
+
+        match (language, self.params.fitness_func_type) {
+            (_, FitnessFuncType::Regression) => {
+                writeln!(source, "    return {};", outputs[0]).unwrap();
+            }
+            (Language::Rust, FitnessFuncType::ClassificationBinary) => {
+                writeln!(source, "    return if {} > 0.0 {{ 1 }} else {{ 0 }};", outputs[0]).unwrap();
+            }
+            (Language::C, FitnessFuncType::ClassificationBinary) => {
+                writeln!(source, "    return {} > 0.0f ? 1 : 0;", outputs[0]).unwrap();
+            }
+            (Language::Rust, FitnessFuncType::ClassificationMultiClass) => {
+                // the last maximum wins, as in `get_argmax`
+                writeln!(source, "    let outputs: [f32; {}] = [{}];", nbr_outputs, outputs.join(", ")).unwrap();
+                writeln!(source, "    let mut class: usize = 0;").unwrap();
+                writeln!(source, "    for i in 1..outputs.len() {{").unwrap();
+                writeln!(source, "        if outputs[i].total_cmp(&outputs[class]).is_ge() {{").unwrap();
+                writeln!(source, "            class = i;").unwrap();
+                writeln!(source, "        }}").unwrap();
+                writeln!(source, "    }}").unwrap();
+                writeln!(source, "    return class;").unwrap();
+            }
+            (Language::C, FitnessFuncType::ClassificationMultiClass) => {
+                writeln!(source, "    const float outputs[{}] = {{{}}};", nbr_outputs, outputs.join(", ")).unwrap();
+                writeln!(source, "    size_t class = 0;").unwrap();
+                writeln!(source, "    for (size_t i = 1; i < {}; i++) {{", nbr_outputs).unwrap();
+                writeln!(source, "        if ({0}_total_order_key(outputs[i]) >= {0}_total_order_key(outputs[class])) {{", function_name).unwrap();
+                writeln!(source, "            class = i;").unwrap();
+                writeln!(source, "        }}").unwrap();
+                writeln!(source, "    }}").unwrap();
+                writeln!(source, "    return class;").unwrap();
+            }
+        }
+        writeln!(source, "}}").unwrap();
+
+        return source;
+    }
+
+    /// Variable holding the value of a node in the generated code.
+    fn operand_name(&self, node_id: usize) -> String {
+        if node_id < self.params.nbr_inputs {
+            return format!("x[{}]", node_id);
+        }
+        return format!("n{}", node_id);
+    }
+}
+
+/// Exact literal of `value`, including the infinite thresholds of classes without finite outputs.
+fn float_literal(value: f32, language: Language) -> String {
+    return match (language, value) {
+        (Language::Rust, v) if v == f32::INFINITY => "f32::INFINITY".to_string(),
+        (Language::Rust, v) if v == f32::NEG_INFINITY => "f32::NEG_INFINITY".to_string(),
+        (Language::Rust, v) => format!("{:?}", v),
+        (Language::C, v) if v == f32::INFINITY => "INFINITY".to_string(),
+        (Language::C, v) if v == f32::NEG_INFINITY => "-INFINITY".to_string(),
+        (Language::C, v) => format!("{:?}f", v),
+    };
+}
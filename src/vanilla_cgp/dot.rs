@@ -0,0 +1,116 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+use crate::analysis::attribute_usage::{AttributeKind, AttributeLabels};
+use crate::utils::node_type::NodeType;
+use crate::vanilla_cgp::chromosome::Chromosome;
+use crate::vanilla_cgp::expression::function_name;
+
+const INACTIVE_COLOR: &str = "grey70";
+
+fn kind_color(kind: AttributeKind) -> &'static str {
+    return match kind {
+        AttributeKind::Original => "palegreen",
+        AttributeKind::Copy => "lightskyblue",
+        AttributeKind::NoisyCopy => "orange",
+        AttributeKind::Noise => "tomato",
+    };
+}
+
+impl Chromosome {
+    /// Graphviz DOT of the active graph, e.g. for `dot -Tsvg`.
+    /// Edges of binary nodes are labeled 0 and 1 with the operand position.
+    /// If `labels` are given, input nodes are colored by their attribute kind.
+    /// With `include_inactive`, the remaining nodes and their connections are drawn greyed out.
+    pub fn to_dot(&self, labels: Option<&AttributeLabels>, include_inactive: bool) -> String {
+        let active: HashSet<usize> = self.active_node_ids().into_iter().collect();
+        let mut dot = String::new();
+
+        writeln!(dot, "digraph chromosome {{").unwrap();
+        writeln!(dot, "    rankdir=LR;").unwrap();
+        writeln!(dot, "    node [style=filled, fillcolor=white];").unwrap();
+
+        for node in &self.nodes_grid {
+            let node_id = node.position;
+            let is_active = active.contains(&node_id);
+            if !is_active && !include_inactive {
+                continue;
+            }
+
+            let mut attributes: Vec<String> = vec![];
+            match node.node_type {
+                NodeType::InputNode => {
+                    let mut label = format!("x{}", node_id);
+                    let mut color = "white";
+                    if let Some(labels) = labels {
+                        let kind = labels.kind(node_id);
+                        match (kind, labels.source(node_id)) {
+                            (AttributeKind::Original, Some(source)) => label.push_str(&format!("\\nattribute {}", source)),
+                            (_, Some(source)) => label.push_str(&format!("\\n{} of {}", kind, source)),
+                            (_, None) => label.push_str(&format!("\\n{}", kind)),
+                        }
+                        color = kind_color(kind);
+                    }
+                    attributes.push(format!("label=\"{}\"", label));
+                    attributes.push("shape=box".to_string());
+                    attributes.push(format!("fillcolor={}", color));
+                }
+                NodeType::ComputationalNode => {
+                    attributes.push(format!("label=\"{}\\n{}\"", function_name(node.function_id), node_id));
+                }
+                NodeType::OutputNode => {
+                    let output_index = node_id - self.params.nbr_inputs - self.params.graph_width;
+                    attributes.push(format!("label=\"out{}\"", output_index));
+                    attributes.push("shape=doublecircle".to_string());
+                }
+            }
+            if !is_active {
+                attributes.push(format!("color={}", INACTIVE_COLOR));
+                attributes.push(format!("fontcolor={}", INACTIVE_COLOR));
+                attributes.push("fillcolor=white".to_string());
+                attributes.push("style=\"filled,dashed\"".to_string());
+            }
+            writeln!(dot, "    n{} [{}];", node_id, attributes.join(", ")).unwrap();
+        }
+
+        for node in &self.nodes_grid {
+            let node_id = node.position;
+            let is_active = active.contains(&node_id);
+            if !is_active && !include_inactive {
+                continue;
+            }
+
+            let mut edges: Vec<(usize, Option<usize>)> = vec![];
+            match node.node_type {
+                NodeType::InputNode => {}
+                NodeType::OutputNode => edges.push((node.connection0, None)),
+                NodeType::ComputationalNode => {
+                    if node.function_id <= 3 {
+                        edges.push((node.connection0, Some(0)));
+                        edges.push((node.connection1, Some(1)));
+                    } else {
+                        edges.push((node.connection0, None));
+                    }
+                }
+            }
+
+            for (source, operand) in edges {
+                let mut attributes: Vec<String> = vec![];
+                if let Some(operand) = operand {
+                    attributes.push(format!("label=\"{}\"", operand));
+                }
+                if !is_active {
+                    attributes.push(format!("color={}", INACTIVE_COLOR));
+                    attributes.push(format!("fontcolor={}", INACTIVE_COLOR));
+                }
+                if attributes.is_empty() {
+                    writeln!(dot, "    n{} -> n{};", source, node_id).unwrap();
+                } else {
+                    writeln!(dot, "    n{} -> n{} [{}];", source, node_id, attributes.join(", ")).unwrap();
+                }
+            }
+        }
+
+        writeln!(dot, "}}").unwrap();
+        return dot;
+    }
+}
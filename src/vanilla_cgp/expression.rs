@@ -0,0 +1,233 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::{Display, Formatter};
+use crate::utils::node_type::NodeType;
+use crate::utils::symbolic_regression_functions as function_set;
+use crate::vanilla_cgp::chromosome::Chromosome;
+
+/// Expression computed by an output node. Binary and unary nodes keep the function id of `Node`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    Input(usize),
+    /// temporary `n<id>` holding the value of a computational node used more than once
+    Node(usize),
+    Const(f32),
+    Unary(usize, Box<Expr>),
+    Binary(usize, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    /// Builds the expression of the given node by walking its connections.
+    /// Connections to nodes in `shared` are not expanded but become `Expr::Node`,
+    /// so every other node occurs at most once and the size stays linear in the graph.
+    pub fn from_node(chromosome: &Chromosome, node_id: usize, shared: &HashSet<usize>) -> Self {
+        let node = &chromosome.nodes_grid[node_id];
+
+        return match node.node_type {
+            NodeType::InputNode => Expr::Input(node_id),
+            NodeType::OutputNode => Expr::operand(chromosome, node.connection0, shared),
+            NodeType::ComputationalNode => {
+                let left = Box::new(Expr::operand(chromosome, node.connection0, shared));
+                if node.function_id <= 3 {
+                    let right = Box::new(Expr::operand(chromosome, node.connection1, shared));
+                    Expr::Binary(node.function_id, left, right)
+                } else {
+                    Expr::Unary(node.function_id, left)
+                }
+            }
+        };
+    }
+
+    fn operand(chromosome: &Chromosome, node_id: usize, shared: &HashSet<usize>) -> Self {
+        if shared.contains(&node_id) {
+            return Expr::Node(node_id);
+        }
+        return Expr::from_node(chromosome, node_id, shared);
+    }
+
+    /// Removes trivial patterns bottom-up without changing the computed values:
+    /// `x + 0 = x`, `x - 0 = x`, `x * 1 = x`, `x / 1 = x` and constant subexpressions.
+    /// With `assume_finite`, also `x - x = 0` and `x / x = 1` (also for `x = 0`, as the division is protected).
+    /// These only hold for finite `x`: for infinite or NaN `x` the program computes NaN instead,
+    /// e.g. for `exp(exp(x0)) - exp(exp(x0))`, so the result may differ from the program.
+    pub fn simplify(self, assume_finite: bool) -> Self {
+        return match self {
+            Expr::Input(_) | Expr::Node(_) | Expr::Const(_) => self,
+            Expr::Unary(function_id, inner) => {
+                let inner = inner.simplify(assume_finite);
+                match inner {
+                    Expr::Const(a) => Expr::Const(apply(function_id, a, None)),
+                    _ => Expr::Unary(function_id, Box::new(inner)),
+                }
+            }
+            Expr::Binary(function_id, left, right) => {
+                let left = left.simplify(assume_finite);
+                let right = right.simplify(assume_finite);
+
+                match (function_id, &left, &right) {
+                    (_, Expr::Const(a), Expr::Const(b)) => Expr::Const(apply(function_id, *a, Some(*b))),
+                    (0, _, Expr::Const(b)) if *b == 0. => left,
+                    (0, Expr::Const(a), _) if *a == 0. => right,
+                    (1, _, Expr::Const(b)) if *b == 0. => left,
+                    (2, _, Expr::Const(b)) if *b == 1. => left,
+                    (2, Expr::Const(a), _) if *a == 1. => right,
+                    (3, _, Expr::Const(b)) if *b == 1. => left,
+                    (1, _, _) if assume_finite && left == right => Expr::Const(0.),
+                    (3, _, _) if assume_finite && left == right => Expr::Const(1.),
+                    _ => Expr::Binary(function_id, Box::new(left), Box::new(right)),
+                }
+            }
+        };
+    }
+
+    /// Temporaries referenced by the expression.
+    fn referenced_nodes(&self, nodes: &mut Vec<usize>) {
+        match self {
+            Expr::Node(id) => nodes.push(*id),
+            Expr::Input(_) | Expr::Const(_) => {}
+            Expr::Unary(_, inner) => inner.referenced_nodes(nodes),
+            Expr::Binary(_, left, right) => {
+                left.referenced_nodes(nodes);
+                right.referenced_nodes(nodes);
+            }
+        }
+    }
+
+    fn precedence(&self) -> usize {
+        return match self {
+            Expr::Binary(0, _, _) | Expr::Binary(1, _, _) => 1,
+            Expr::Binary(_, _, _) => 2,
+            _ => 3,
+        };
+    }
+}
+
+/// Operator or name of a function of the function set.
+pub fn function_name(function_id: usize) -> &'static str {
+    return match function_id {
+        0 => "+",
+        1 => "-",
+        2 => "*",
+        3 => "/",
+        4 => "sin",
+        5 => "cos",
+        6 => "ln",  // protected: ln(|x|), 1 for x = 0
+        7 => "exp",
+        _ => panic!("wrong function id: {}", function_id),
+    };
+}
+
+/// Applies a function of the function set to single values, with the same semantics as `Node::execute`.
+fn apply(function_id: usize, a: f32, b: Option<f32>) -> f32 {
+    let a = vec![a];
+    let b = vec![b.unwrap_or(0.)];
+    let result = match function_id {
+        0 => function_set::add(&a, &b),
+        1 => function_set::subtract(&a, &b),
+        2 => function_set::mul(&a, &b),
+        3 => function_set::div(&a, &b),
+        4 => function_set::sin(&a),
+        5 => function_set::cos(&a),
+        6 => function_set::ln(&a),
+        7 => function_set::exp(&a),
+        _ => panic!("wrong function id: {}", function_id),
+    };
+    return result[0];
+}
+
+impl Display for Expr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expr::Input(id) => write!(f, "x{}", id),
+            Expr::Node(id) => write!(f, "n{}", id),
+            Expr::Const(value) => write!(f, "{}", value),
+            Expr::Unary(function_id, inner) => {
+                write!(f, "{}({})", function_name(*function_id), inner)
+            }
+            Expr::Binary(function_id, left, right) => {
+                let operator = function_name(*function_id);
+                let precedence = self.precedence();
+
+                if left.precedence() < precedence {
+                    write!(f, "({})", left)?;
+                } else {
+                    write!(f, "{}", left)?;
+                }
+                write!(f, " {} ", operator)?;
+                // - and / are not associative, so an operand of the same precedence on the right needs parentheses
+                let needs_parentheses = right.precedence() < precedence
+                    || (right.precedence() == precedence && (*function_id == 1 || *function_id == 3));
+                if needs_parentheses {
+                    write!(f, "({})", right)
+                } else {
+                    write!(f, "{}", right)
+                }
+            }
+        }
+    }
+}
+
+impl Chromosome {
+    /// One infix expression per output node, e.g. `sin(x3) * (x1 - x7)`. Inputs are named by their node id.
+    /// Computational nodes used more than once are computed once in a temporary named by their node id,
+    /// e.g. `let n12 = x3 * x4; n12 + sin(n12)`. See `Expr::simplify` for `simplify` and `assume_finite`.
+    pub fn to_expressions(&self, simplify: bool, assume_finite: bool) -> Vec<String> {
+        let shared = self.shared_nodes();
+        let build = |node_id: usize| {
+            let expr = Expr::from_node(self, node_id, &shared);
+            match simplify {
+                true => expr.simplify(assume_finite),
+                false => expr,
+            }
+        };
+        let temporaries: BTreeMap<usize, Expr> = shared.iter().map(|node_id| (*node_id, build(*node_id))).collect();
+
+        return self.output_node_ids
+            .iter()
+            .map(|output_node_id| {
+                let expr = build(*output_node_id);
+
+                // temporaries needed by the output, directly or through other temporaries
+                let mut needed: HashSet<usize> = HashSet::new();
+                let mut to_visit: Vec<usize> = vec![];
+                expr.referenced_nodes(&mut to_visit);
+                while let Some(node_id) = to_visit.pop() {
+                    if needed.insert(node_id) {
+                        temporaries[&node_id].referenced_nodes(&mut to_visit);
+                    }
+                }
+
+                // connections point to lower node ids, so ascending ids define every temporary before its use
+                let mut result = String::new();
+                for (node_id, temporary) in temporaries.iter().filter(|(node_id, _)| needed.contains(node_id)) {
+                    result.push_str(&format!("let n{} = {}; ", node_id, temporary));
+                }
+                result.push_str(&expr.to_string());
+                result
+            })
+            .collect();
+    }
+
+    /// Active computational nodes that are connected to more than one node.
+    fn shared_nodes(&self) -> HashSet<usize> {
+        let mut uses: BTreeMap<usize, usize> = BTreeMap::new();
+        for node_id in self.active_node_ids() {
+            let node = &self.nodes_grid[node_id];
+            let connections = match node.node_type {
+                NodeType::InputNode => vec![],
+                NodeType::OutputNode => vec![node.connection0],
+                NodeType::ComputationalNode if node.function_id <= 3 => vec![node.connection0, node.connection1],
+                NodeType::ComputationalNode => vec![node.connection0],
+            };
+            for connection in connections {
+                if self.nodes_grid[connection].node_type == NodeType::ComputationalNode {
+                    *uses.entry(connection).or_insert(0) += 1;
+                }
+            }
+        }
+        return uses
+            .into_iter()
+            .filter(|(_, count)| *count > 1)
+            .map(|(node_id, _)| node_id)
+            .collect();
+    }
+}
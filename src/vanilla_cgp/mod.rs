@@ -1,2 +1,5 @@
 pub mod node;
 pub mod chromosome;
+pub mod expression;
+pub mod dot;
+pub mod codegen;
@@ -1,19 +1,28 @@
 use std::fmt::{Display, Formatter};
 use rand::Rng;
+use serde::{Deserialize, Serialize};
+use crate::global_params::{default_rows, CgpParameters as g_params};
 use crate::utils::symbolic_regression_functions as function_set;
+use crate::utils::connection_sampling::ConnectionSampling;
 use crate::utils::node_type::NodeType;
 use crate::utils::utility_funcs::gen_random_number_for_node;
 
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Node {
     pub position: usize,
     pub node_type: NodeType,
     pub nbr_inputs: usize,
     pub graph_width: usize,
+    #[serde(default = "default_rows")]
+    pub rows: usize,
+    #[serde(default)]
+    pub levels_back: Option<usize>,
     pub function_id: usize,
     pub connection0: usize,
     pub connection1: usize,
+    #[serde(default)]
+    pub connection_sampling: ConnectionSampling,
 }
 
 impl Display for Node {
@@ -26,38 +35,62 @@ impl Display for Node {
 }
 
 impl Node {
-    pub fn new(position: usize,
-               nbr_inputs: usize,
-               graph_width: usize,
-               node_type: NodeType) -> Self {
-        let function_id: usize = rand::thread_rng().gen_range(0..=7);
-        let connection0: usize;
-        let connection1: usize;
-
-        match node_type {
-            NodeType::InputNode => {
-                connection0 = usize::MAX;
-                connection1 = usize::MAX;
-            },
+    pub fn new<R: Rng>(position: usize,
+                       params: &g_params,
+                       node_type: NodeType,
+                       rng: &mut R) -> Self {
+        let function_id: usize = rng.gen_range(0..=7);
+        let nbr_inputs = params.nbr_inputs;
+        let connection_sampling = params.connection_sampling;
+
+        let mut node = Self {
+            position,
+            node_type,
+            nbr_inputs,
+            graph_width: params.graph_width,
+            rows: params.rows,
+            levels_back: params.levels_back,
+            function_id,
+            connection0: usize::MAX,
+            connection1: usize::MAX,
+            connection_sampling,
+        };
+
+        match node.node_type {
+            NodeType::InputNode => {},
             NodeType::ComputationalNode => {
-                connection0 = rand::thread_rng().gen_range(0..position);
-                connection1 = rand::thread_rng().gen_range(0..position);
+                let (lowest, highest) = node.connection_range();
+                node.connection0 = connection_sampling.sample(lowest, highest, nbr_inputs, None, rng);
+                node.connection1 = connection_sampling.sample(lowest, highest, nbr_inputs, None, rng);
             },
             NodeType::OutputNode => {
-                connection0 = rand::thread_rng().gen_range(0..nbr_inputs + graph_width);
-                connection1 = usize::MAX;
+                let (lowest, highest) = node.connection_range();
+                node.connection0 = connection_sampling
+                    .for_outputs()
+                    .sample(lowest, highest, nbr_inputs, None, rng);
             },
         }
 
-        Self {
-            position,
-            node_type,
-            nbr_inputs,
-            graph_width,
-            function_id,
-            connection0,
-            connection1,
+        return node;
+    }
+
+    /// Node ids the connections may point to, `lowest..highest`.
+    /// A computational node in column j connects to the nodes of the `levels_back` columns before it,
+    /// and to the inputs only if j < `levels_back`. Output nodes connect to any input or computational node.
+    pub fn connection_range(&self) -> (usize, usize) {
+        assert!(self.node_type != NodeType::InputNode);
+
+        if self.node_type == NodeType::OutputNode {
+            return (0, self.nbr_inputs + self.graph_width);
         }
+
+        let column = (self.position - self.nbr_inputs) / self.rows;
+        let highest = self.nbr_inputs + column * self.rows;
+        let lowest = match self.levels_back {
+            Some(levels_back) if column >= levels_back => self.nbr_inputs + (column - levels_back) * self.rows,
+            _ => 0,
+        };
+        return (lowest, highest);
     }
 
     pub fn execute(&self, conn1_value: &Vec<f32>, conn2_value: Option<&Vec<f32>>) -> Vec<f32> {
@@ -76,48 +109,93 @@ impl Node {
         }
     }
 
-    pub fn mutate(&mut self) {
+    pub fn mutate<R: Rng>(&mut self, rng: &mut R) {
         assert!(self.node_type != NodeType::InputNode);
 
         match self.node_type {
-            NodeType::OutputNode => self.mutate_output_node(),
-            NodeType::ComputationalNode => self.mutate_computational_node(),
+            NodeType::OutputNode => self.mutate_output_node(rng),
+            NodeType::ComputationalNode => self.mutate_computational_node(rng),
             _ => { panic!("Trying to mutate input node") }
         }
     }
 
-    fn mutate_connection(connection: &mut usize, upper_range: usize) {
-        *connection = gen_random_number_for_node(*connection,
-                                                 upper_range);
+    fn mutate_connection<R: Rng>(connection: &mut usize,
+                                 (lowest, highest): (usize, usize),
+                                 nbr_inputs: usize,
+                                 connection_sampling: ConnectionSampling,
+                                 rng: &mut R) {
+        *connection = connection_sampling.sample(lowest,
+                                                 highest,
+                                                 nbr_inputs,
+                                                 Some(*connection),
+                                                 rng);
 
     }
 
-    fn mutate_function(&mut self) {
-        self.function_id = gen_random_number_for_node(self.function_id, 8);
+    fn mutate_function<R: Rng>(&mut self, rng: &mut R) {
+        self.function_id = gen_random_number_for_node(self.function_id, 8, rng);
     }
 
-    fn mutate_output_node(&mut self) {
+    fn mutate_output_node<R: Rng>(&mut self, rng: &mut R) {
+        let connection_range = self.connection_range();
         Node::mutate_connection(&mut self.connection0,
-                                self.graph_width + self.nbr_inputs);
+                                connection_range,
+                                self.nbr_inputs,
+                                self.connection_sampling.for_outputs(),
+                                rng);
 
         assert!(self.connection0 < self.position);
     }
 
-    fn mutate_computational_node(&mut self) {
-        let rand_nbr = rand::thread_rng().gen_range(0..=2);
-        match rand_nbr {
-            0 => Node::mutate_connection(&mut self.connection0,
-                                         self.position),
+    fn mutate_computational_node<R: Rng>(&mut self, rng: &mut R) {
+        let rand_nbr = rng.gen_range(0..=2);
+        self.mutate_gene(rand_nbr, rng);
+    }
+
+    /// Number of genes: connection0, connection1 and function for computational nodes,
+    /// only connection0 for output nodes.
+    pub fn nbr_genes(&self) -> usize {
+        return match self.node_type {
+            NodeType::InputNode => 0,
+            NodeType::ComputationalNode => 3,
+            NodeType::OutputNode => 1,
+        };
+    }
+
+    /// Whether changing the gene changes the node's output; the second connection of unary functions is unused.
+    pub fn is_gene_used(&self, gene: usize) -> bool {
+        return match gene {
+            1 => self.function_id <= 3,
+            _ => true,
+        };
+    }
+
+    /// Changes gene `gene` (0: connection0, 1: connection1, 2: function) to a different value.
+    pub fn mutate_gene<R: Rng>(&mut self, gene: usize, rng: &mut R) {
+        let connection_range = self.connection_range();
+        match (&self.node_type, gene) {
+            (NodeType::OutputNode, 0) => self.mutate_output_node(rng),
 
-            1 => Node::mutate_connection(&mut self.connection1,
-                                         self.position),
+            (NodeType::ComputationalNode, 0) => Node::mutate_connection(&mut self.connection0,
+                                                                        connection_range,
+                                                                        self.nbr_inputs,
+                                                                        self.connection_sampling,
+                                                                        rng),
 
-            2 => self.mutate_function(),
+            (NodeType::ComputationalNode, 1) => Node::mutate_connection(&mut self.connection1,
+                                                                        connection_range,
+                                                                        self.nbr_inputs,
+                                                                        self.connection_sampling,
+                                                                        rng),
 
-            _ => { panic!("Mutation: output node something wrong") }
+            (NodeType::ComputationalNode, 2) => self.mutate_function(rng),
+
+            _ => { panic!("Mutation: node {} has no gene {}", self.position, gene) }
         };
 
-        assert!(self.connection0 < self.position);
-        assert!(self.connection1 < self.position);
+        if self.node_type == NodeType::ComputationalNode {
+            assert!(self.connection0 < self.position);
+            assert!(self.connection1 < self.position);
+        }
     }
 }
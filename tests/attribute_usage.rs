@@ -0,0 +1,95 @@
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use cgp::analysis::attribute_usage::{AttributeKind, AttributeLabels};
+use cgp::analysis::usage_over_time::usage_over_time;
+use cgp::datasets::dataset_utils::{add_redundancies, RedundancyMapping, RedundancyType};
+use cgp::utils::runner::InputUsageSnapshot;
+use cgp::utils::utility_funcs::transpose;
+
+fn mapping(entries: &[(usize, Vec<usize>)]) -> RedundancyMapping {
+    let mut mapping = RedundancyMapping::default();
+    for (source, input_ids) in entries {
+        mapping.insert(*source, input_ids.clone());
+    }
+    return mapping;
+}
+
+fn kinds_and_sources(labels: &AttributeLabels) -> Vec<(AttributeKind, Option<usize>)> {
+    return (0..labels.nbr_inputs())
+        .map(|input_id| (labels.kind(input_id), labels.source(input_id)))
+        .collect();
+}
+
+// three original attributes and two redundant inputs, 0 -> input 1 and 2 -> input 4:
+// the inputs are original 0, redundant, original 1, original 2, redundant
+
+#[test]
+fn labels_copies() {
+    let labels = AttributeLabels::new(5, &mapping(&[(0, vec![1]), (2, vec![4])]), RedundancyType::Copy);
+    assert_eq!(kinds_and_sources(&labels), vec![
+        (AttributeKind::Original, Some(0)),
+        (AttributeKind::Copy, Some(0)),
+        (AttributeKind::Original, Some(1)),
+        (AttributeKind::Original, Some(2)),
+        (AttributeKind::Copy, Some(2)),
+    ]);
+}
+
+#[test]
+fn labels_noisy_copies() {
+    let labels = AttributeLabels::new(5, &mapping(&[(0, vec![1]), (2, vec![4])]), RedundancyType::NoisyCopy);
+    assert_eq!(kinds_and_sources(&labels), vec![
+        (AttributeKind::Original, Some(0)),
+        (AttributeKind::NoisyCopy, Some(0)),
+        (AttributeKind::Original, Some(1)),
+        (AttributeKind::Original, Some(2)),
+        (AttributeKind::NoisyCopy, Some(2)),
+    ]);
+}
+
+#[test]
+fn labels_noise() {
+    // all noise attributes are listed under the key 0
+    let labels = AttributeLabels::new(5, &mapping(&[(0, vec![1, 4])]), RedundancyType::Noise);
+    assert_eq!(kinds_and_sources(&labels), vec![
+        (AttributeKind::Original, Some(0)),
+        (AttributeKind::Noise, None),
+        (AttributeKind::Original, Some(1)),
+        (AttributeKind::Original, Some(2)),
+        (AttributeKind::Noise, None),
+    ]);
+}
+
+#[test]
+fn labels_match_the_bloated_data() {
+    let mut rng = ChaCha8Rng::seed_from_u64(0);
+    let data: Vec<Vec<f32>> = (0..10)
+        .map(|_| (0..4).map(|_| rng.gen_range(-1.0..1.0)).collect())
+        .collect();
+    let (bloated, _, mapping) = add_redundancies(data.clone(), None, 1.5, RedundancyType::Copy, &mut rng);
+    let (columns, bloated_columns) = (transpose(data), transpose(bloated));
+
+    let labels = AttributeLabels::new(bloated_columns.len(), &mapping, RedundancyType::Copy);
+    for (input_id, column) in bloated_columns.iter().enumerate() {
+        let source = labels.source(input_id).unwrap();
+        assert_eq!(*column, columns[source], "input {} is labelled as {:?} of {}", input_id, labels.kind(input_id), source);
+    }
+}
+
+#[test]
+fn tracks_usage_over_time() {
+    let labels = AttributeLabels::new(5, &mapping(&[(0, vec![1]), (2, vec![4])]), RedundancyType::Copy);
+    let history: Vec<InputUsageSnapshot> = [(0, vec![0]), (10, vec![0, 1]), (20, vec![1, 4]), (30, vec![1, 4])]
+        .into_iter()
+        .map(|(iteration, active_inputs)| InputUsageSnapshot { iteration, active_inputs })
+        .collect();
+
+    let usage = usage_over_time(&history, &labels);
+    assert_eq!(usage.iterations, vec![0, 10, 20, 30]);
+    assert_eq!(usage.curves[0].used, vec![true, true, false, false]);
+    assert_eq!((usage.curves[0].picked_up.clone(), usage.curves[0].dropped.clone()), (vec![0], vec![20]));
+    assert_eq!((usage.curves[1].picked_up.clone(), usage.curves[1].dropped.clone()), (vec![10], vec![]));
+    assert_eq!(usage.active_per_kind["original"], vec![1, 1, 0, 0]);
+    assert_eq!(usage.active_per_kind["copy"], vec![0, 1, 2, 2]);
+    assert_eq!(usage.active_per_kind["noise"], vec![0, 0, 0, 0]);
+}
@@ -0,0 +1,162 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use cgp::datasets::fitness_metric_type::FitnessFuncType;
+use cgp::global_params::CgpParameters;
+use cgp::utils::checkpoint::Checkpoint;
+use cgp::utils::connection_sampling::ConnectionSampling;
+use cgp::utils::multiclass_decoding::MulticlassDecoding;
+use cgp::utils::mutation_strategy::{MutationStrategy, RateControl};
+use cgp::utils::run_logger::{IterationRecord, LogFormat, RunLogger};
+use cgp::utils::runner::Runner;
+use cgp::utils::selection::Selection;
+
+const NBR_INPUTS: usize = 4;
+/// iterations before the interruption; the runs are continued to twice as many
+const ITERATIONS: usize = 100;
+const CHECKPOINT_INTERVAL: usize = 25;
+const LOG_INTERVAL: usize = 10;
+
+fn params() -> CgpParameters {
+    return CgpParameters {
+        graph_width: 30,
+        rows: 1,
+        levels_back: None,
+        mu: 1,
+        lambda: 4,
+        eval_after_iterations: LOG_INTERVAL,
+        nbr_inputs: NBR_INPUTS,
+        nbr_outputs: 1,
+        nbr_classes: 2,
+        fitness_func_type: FitnessFuncType::ClassificationBinary,
+        connection_sampling: ConnectionSampling::Uniform,
+        multiclass_decoding: MulticlassDecoding::Argmax,
+        class_costs: None,
+    };
+}
+
+/// Train and test samples, one row per sample, whose class depends on the first two attributes.
+fn data() -> (Vec<Vec<f32>>, Vec<usize>, Vec<Vec<f32>>, Vec<usize>) {
+    let mut rng = ChaCha8Rng::seed_from_u64(0);
+    let mut samples = |nbr_samples: usize| -> (Vec<Vec<f32>>, Vec<usize>) {
+        let samples: Vec<Vec<f32>> = (0..nbr_samples)
+            .map(|_| (0..NBR_INPUTS).map(|_| rng.gen_range(-1.0..1.0)).collect())
+            .collect();
+        let labels = samples.iter().map(|sample| (sample[0] * sample[1] > 0.1) as usize).collect();
+        return (samples, labels);
+    };
+    let (train_data, train_label) = samples(60);
+    let (test_data, test_label) = samples(20);
+    return (train_data, train_label, test_data, test_label);
+}
+
+fn runner(configure: fn(&mut Runner)) -> Runner {
+    let (train_data, train_label, test_data, test_label) = data();
+    let mut runner = Runner::new(params(), train_data, train_label, Some(test_data), Some(test_label), 9);
+    configure(&mut runner);
+    return runner;
+}
+
+fn record(runner: &Runner) -> IterationRecord {
+    return IterationRecord {
+        iteration: runner.get_iteration(),
+        train_fitness: runner.get_parent_error(),
+        active_nodes: runner.get_parent().active_nodes.len(),
+        redundant_inputs: 0,
+        elapsed_seconds: 0.,
+        mutation_rate: runner.get_mutation_rate(),
+        input_penalty: runner.get_parent_input_penalty(),
+    };
+}
+
+/// The training loop of a fold: logs and checkpoints as `main` does, until `iterations` are done.
+fn train(mut runner: Runner, iterations: usize, logger: &mut RunLogger, checkpoint_path: &Path) -> Runner {
+    while runner.get_iteration() < iterations {
+        if runner.get_iteration().is_multiple_of(LOG_INTERVAL) {
+            logger.log(&record(&runner));
+        }
+        runner.learn_step();
+
+        if runner.get_iteration().is_multiple_of(CHECKPOINT_INTERVAL) {
+            let checkpoint = Checkpoint {
+                seed: 9,
+                fold: 0,
+                test_indices: vec![],
+                run_log_len: logger.checkpoint(),
+                elapsed_seconds: 0.,
+                genome_log_len: None,
+                config: None,
+                runner,
+            };
+            checkpoint.save(checkpoint_path);
+            runner = checkpoint.runner;
+        }
+    }
+    return runner;
+}
+
+fn work_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("cgp_checkpoint_{}_{}", std::process::id(), name));
+    fs::create_dir_all(&dir).unwrap();
+    return dir;
+}
+
+/// Runs `2 * ITERATIONS` at once, and runs `ITERATIONS` plus a few iterations past the last checkpoint,
+/// which are lost as if the process was killed, and resumes to `2 * ITERATIONS` from the checkpoint.
+/// The parent of a checkpoint has no cached node outputs, so after resuming the first offspring are
+/// evaluated from scratch; both runs must still agree in every bit.
+fn check_resume(name: &str, configure: fn(&mut Runner)) {
+    let dir = work_dir(name);
+    let (straight_log, resumed_log, checkpoint_path) = (dir.join("straight.jsonl"), dir.join("resumed.jsonl"), dir.join("checkpoint.json"));
+
+    let mut logger = RunLogger::new(straight_log.clone(), LogFormat::Jsonl);
+    let straight = train(runner(configure), 2 * ITERATIONS, &mut logger, &dir.join("straight_checkpoint.json"));
+    logger.log(&record(&straight));
+    logger.close_writer();
+
+    let mut logger = RunLogger::new(resumed_log.clone(), LogFormat::Jsonl);
+    let interrupted = train(runner(configure), ITERATIONS + 2 * LOG_INTERVAL, &mut logger, &checkpoint_path);
+    logger.close_writer();
+    assert_eq!(interrupted.get_iteration(), ITERATIONS + 2 * LOG_INTERVAL);
+
+    let checkpoint = Checkpoint::load(&checkpoint_path).unwrap();
+    let mut resumed = checkpoint.runner;
+    assert_eq!(resumed.get_iteration(), ITERATIONS);
+    assert!(resumed.get_parent().cached_outputs.is_empty());
+
+    let (train_data, train_label, test_data, test_label) = data();
+    resumed.attach_data(train_data, train_label, Some(test_data), Some(test_label));
+    let mut logger = RunLogger::resume(resumed_log.clone(), LogFormat::Jsonl, checkpoint.run_log_len);
+    let resumed = train(resumed, 2 * ITERATIONS, &mut logger, &checkpoint_path);
+    logger.log(&record(&resumed));
+    logger.close_writer();
+
+    assert_eq!(resumed.get_parent_error().to_bits(), straight.get_parent_error().to_bits());
+    assert_eq!(resumed.get_test_fitness().to_bits(), straight.get_test_fitness().to_bits());
+    assert_eq!(serde_json::to_value(resumed.get_parent()).unwrap(), serde_json::to_value(straight.get_parent()).unwrap());
+    assert_eq!(fs::read_to_string(&resumed_log).unwrap(), fs::read_to_string(&straight_log).unwrap());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn resumed_run_equals_uninterrupted_run() {
+    check_resume("fitness", |_| {});
+}
+
+#[test]
+fn resumed_run_with_adaptive_mutation_rates_equals_uninterrupted_run() {
+    check_resume("self_adaptive", |runner| {
+        runner.set_mutation_strategy(MutationStrategy::Point { rate: 0.05, force_active: true });
+        runner.set_rate_control(RateControl::SelfAdaptive { tau: 0.2, min_rate: 0.001, max_rate: 0.5 });
+    });
+}
+
+#[test]
+fn resumed_nsga2_run_equals_uninterrupted_run() {
+    check_resume("nsga2", |runner| {
+        runner.set_mutation_strategy(MutationStrategy::Point { rate: 0.05, force_active: false });
+        runner.set_selection(Selection::Nsga2 { population_size: 8 });
+    });
+}
@@ -0,0 +1,131 @@
+use std::fs;
+use std::path::PathBuf;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use cgp::datasets::dataset_utils::LabelEncoder;
+use cgp::utils::class_costs::ClassCosts;
+use cgp::utils::fitness_metrics::{fitness_class_weighted, fitness_cost_matrix, fitness_regression};
+
+const NBR_SAMPLES: usize = 500;
+
+/// Random labels and predictions of `nbr_classes` classes, about half of them correct.
+fn predictions_and_labels(rng: &mut ChaCha8Rng, nbr_classes: usize) -> (Vec<usize>, Vec<usize>) {
+    let labels: Vec<usize> = (0..NBR_SAMPLES).map(|_| rng.gen_range(0..nbr_classes)).collect();
+    let predictions: Vec<usize> = labels
+        .iter()
+        .map(|label| match rng.gen_bool(0.5) {
+            true => *label,
+            false => rng.gen_range(0..nbr_classes),
+        })
+        .collect();
+    return (predictions, labels);
+}
+
+fn error_rate(predictions: &Vec<usize>, labels: &Vec<usize>) -> f32 {
+    let errors = predictions.iter().zip(labels.iter()).filter(|(x, y)| x != y).count();
+    return errors as f32 / labels.len() as f32;
+}
+
+fn uniform_costs(nbr_classes: usize, cost: f32) -> Vec<Vec<f32>> {
+    return (0..nbr_classes)
+        .map(|true_class| (0..nbr_classes)
+            .map(|predicted_class| if true_class == predicted_class { 0. } else { cost })
+            .collect())
+        .collect();
+}
+
+fn write_file(name: &str, contents: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("cgp_class_costs_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    fs::write(&path, contents).unwrap();
+    return path;
+}
+
+#[test]
+fn uniform_cost_matrix_is_error_rate() {
+    let mut rng = ChaCha8Rng::seed_from_u64(0);
+    for nbr_classes in [2, 3, 7] {
+        let (predictions, labels) = predictions_and_labels(&mut rng, nbr_classes);
+        let expected = error_rate(&predictions, &labels);
+
+        for cost in [1., 0.5, 3.] {
+            let fitness = fitness_cost_matrix(&predictions, &labels, &uniform_costs(nbr_classes, cost));
+            assert!((fitness - expected).abs() < 1e-6, "{} != {}", fitness, expected);
+        }
+    }
+}
+
+#[test]
+fn uniform_class_weights_are_error_rate() {
+    let mut rng = ChaCha8Rng::seed_from_u64(1);
+    for nbr_classes in [2, 3, 7] {
+        let (predictions, labels) = predictions_and_labels(&mut rng, nbr_classes);
+        let expected = error_rate(&predictions, &labels);
+
+        for weight in [1., 0.5, 3.] {
+            let fitness = fitness_class_weighted(&predictions, &labels, &vec![weight; nbr_classes]);
+            assert!((fitness - expected).abs() < 1e-6, "{} != {}", fitness, expected);
+        }
+    }
+}
+
+#[test]
+fn uniform_costs_match_binary_fitness() {
+    let mut rng = ChaCha8Rng::seed_from_u64(2);
+    let (predictions, labels) = predictions_and_labels(&mut rng, 2);
+    let expected = fitness_regression(&predictions, &labels);
+
+    assert!((fitness_cost_matrix(&predictions, &labels, &uniform_costs(2, 1.)) - expected).abs() < 1e-6);
+    assert!((fitness_class_weighted(&predictions, &labels, &vec![1., 1.]) - expected).abs() < 1e-6);
+}
+
+#[test]
+fn costs_weight_the_errors() {
+    let labels = vec![0, 0, 1, 1];
+    let predictions = vec![0, 1, 1, 1];
+
+    // the only error is a true 0 predicted as 1
+    let costs = vec![vec![0., 4.], vec![1., 0.]];
+    assert_eq!(fitness_cost_matrix(&predictions, &labels, &costs), 0.25);
+    let costs = vec![vec![0., 1.], vec![4., 0.]];
+    assert_eq!(fitness_cost_matrix(&predictions, &labels, &costs), 1. / 16.);
+
+    assert_eq!(fitness_class_weighted(&predictions, &labels, &vec![3., 1.]), 3. / 8.);
+}
+
+#[test]
+fn loads_costs_by_label() {
+    let label_encoder = LabelEncoder::fit(&["B", "M", "B"]);
+
+    let path = write_file("matrix.toml", "type = \"matrix\"\n[costs.M]\nB = 5.0\n");
+    assert_eq!(ClassCosts::load(&path, &label_encoder), ClassCosts::Matrix {
+        costs: vec![vec![0., 1.], vec![5., 0.]],
+    });
+
+    let path = write_file("weights.json", "{\"type\": \"weights\", \"weights\": {\"M\": 2.5}}");
+    assert_eq!(ClassCosts::load(&path, &label_encoder), ClassCosts::Weights {
+        weights: vec![1., 2.5],
+    });
+
+    // numeric labels are matched by value
+    let label_encoder = LabelEncoder::fit(&["1", "2", "3"]);
+    let path = write_file("numeric.toml", "type = \"weights\"\n[weights]\n\"3.0\" = 4.0\n");
+    assert_eq!(ClassCosts::load(&path, &label_encoder), ClassCosts::Weights {
+        weights: vec![1., 1., 4.],
+    });
+
+    // an empty matrix is the plain error rate
+    let path = write_file("empty.toml", "type = \"matrix\"\n[costs]\n");
+    let (predictions, labels) = predictions_and_labels(&mut ChaCha8Rng::seed_from_u64(3), 3);
+    let fitness = ClassCosts::load(&path, &label_encoder).fitness(&predictions, &labels);
+    assert!((fitness - error_rate(&predictions, &labels)).abs() < 1e-6);
+}
+
+#[test]
+#[should_panic(expected = "unknown class X")]
+fn rejects_unknown_labels() {
+    let label_encoder = LabelEncoder::fit(&["B", "M"]);
+    let path = write_file("unknown.toml", "type = \"weights\"\n[weights]\nX = 2.0\n");
+    ClassCosts::load(&path, &label_encoder);
+}
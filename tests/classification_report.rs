@@ -0,0 +1,104 @@
+use cgp::analysis::classification_report::{ClassificationReport, CrossValidationReport, FoldReport};
+
+fn assert_close(actual: f32, expected: f32) {
+    assert!((actual - expected).abs() < 1e-6, "{} != {}", actual, expected);
+}
+
+/// (precision, recall, f1, support) of every class.
+fn class_metrics(report: &ClassificationReport) -> Vec<(f32, f32, f32, usize)> {
+    return report.classes
+        .iter()
+        .map(|metrics| (metrics.precision, metrics.recall, metrics.f1, metrics.support))
+        .collect();
+}
+
+fn assert_class_metrics(report: &ClassificationReport, expected: &[(f32, f32, f32, usize)]) {
+    for (class, (actual, expected)) in class_metrics(report).iter().zip(expected.iter()).enumerate() {
+        assert_close(actual.0, expected.0);
+        assert_close(actual.1, expected.1);
+        assert_close(actual.2, expected.2);
+        assert_eq!(actual.3, expected.3, "support of class {}", class);
+    }
+}
+
+#[test]
+fn metrics_of_a_binary_confusion_matrix() {
+    // tp = 2, tn = 5, fp = 1, fn = 2 for class 1
+    let report = ClassificationReport::from_confusion_matrix(vec![vec![5, 1], vec![2, 2]]);
+
+    assert_class_metrics(&report, &[(5. / 7., 5. / 6., 10. / 13., 6), (2. / 3., 0.5, 4. / 7., 4)]);
+    assert_close(report.accuracy, 0.7);
+    assert_close(report.balanced_accuracy, (5. / 6. + 0.5) / 2.);
+    assert_close(report.macro_f1, (10. / 13. + 4. / 7.) / 2.);
+    // Gorodkin's MCC reduces to (tp * tn - fp * fn) / sqrt((tp + fp)(tp + fn)(tn + fp)(tn + fn))
+    assert_close(report.mcc, 8. / (3f32 * 4. * 6. * 7.).sqrt());
+}
+
+#[test]
+fn classes_that_never_occur_are_left_out_of_the_means() {
+    // class 2 is predicted once but never occurs
+    let report = ClassificationReport::from_confusion_matrix(vec![vec![3, 1, 0], vec![1, 2, 1], vec![0, 0, 0]]);
+
+    assert_class_metrics(&report, &[(0.75, 0.75, 0.75, 4), (2. / 3., 0.5, 4. / 7., 4), (0., 0., 0., 0)]);
+    assert_close(report.accuracy, 5. / 8.);
+    assert_close(report.balanced_accuracy, (0.75 + 0.5) / 2.);
+    assert_close(report.macro_f1, (0.75 + 4. / 7.) / 2.);
+    // correct * total - sum of predicted * true counts = 5 * 8 - (4 * 4 + 3 * 4 + 1 * 0),
+    // divided by sqrt((8² - (4² + 3² + 1²)) * (8² - (4² + 4² + 0²)))
+    assert_close(report.mcc, 12. / (38f32 * 32.).sqrt());
+}
+
+#[test]
+fn undefined_mcc_is_zero() {
+    // every sample is predicted as class 0
+    let report = ClassificationReport::from_confusion_matrix(vec![vec![3, 0], vec![2, 0]]);
+    assert_class_metrics(&report, &[(0.6, 1., 0.75, 3), (0., 0., 0., 2)]);
+    assert_close(report.balanced_accuracy, 0.5);
+    assert_close(report.macro_f1, 0.375);
+    assert_eq!(report.mcc, 0.);
+
+    // every sample belongs to class 0
+    let report = ClassificationReport::from_confusion_matrix(vec![vec![2, 1], vec![0, 0]]);
+    assert_close(report.balanced_accuracy, 2. / 3.);
+    assert_close(report.macro_f1, 0.8);
+    assert_eq!(report.mcc, 0.);
+
+    let report = ClassificationReport::from_confusion_matrix(vec![vec![0, 0], vec![0, 0]]);
+    assert_eq!((report.accuracy, report.balanced_accuracy, report.macro_f1, report.mcc), (0., 0., 0., 0.));
+}
+
+#[test]
+fn confusion_matrix_grows_with_labels_and_predictions() {
+    let report = ClassificationReport::new(&vec![0, 2, 1], &vec![0, 0, 3], 2);
+    assert_eq!(report.confusion_matrix, vec![vec![1, 0, 1, 0], vec![0; 4], vec![0; 4], vec![0, 1, 0, 0]]);
+}
+
+fn fold(train: Vec<Vec<usize>>, test: Option<Vec<Vec<usize>>>) -> FoldReport {
+    return FoldReport {
+        train: ClassificationReport::from_confusion_matrix(train),
+        test: test.map(ClassificationReport::from_confusion_matrix),
+        class_labels: vec!["a".to_string(), "b".to_string(), "c".to_string()],
+    };
+}
+
+#[test]
+fn pools_confusion_matrices_of_different_sizes() {
+    // class 2 only occurs in the second fold, the first fold has no test data
+    let folds = vec![
+        fold(vec![vec![1, 0], vec![1, 2]], None),
+        fold(vec![vec![1, 0, 1], vec![0, 1, 0], vec![0, 0, 2]], Some(vec![vec![1, 1, 0], vec![0, 1, 0], vec![0, 0, 1]])),
+    ];
+    let report = CrossValidationReport::new(&folds);
+
+    assert_eq!(report.nbr_folds, 2);
+    assert_eq!(report.class_labels, ["a", "b", "c"]);
+    assert_eq!(report.train.confusion_matrix, vec![vec![2, 0, 1], vec![1, 3, 0], vec![0, 0, 2]]);
+    assert_close(report.train.accuracy, 7. / 9.);
+    assert_eq!(report.test.unwrap().confusion_matrix, vec![vec![1, 1, 0], vec![0, 1, 0], vec![0, 0, 1]]);
+
+    // accuracies 3 / 4 and 4 / 5 of the folds
+    assert_close(report.train_folds.accuracy.mean, 0.775);
+    assert_close(report.train_folds.accuracy.std, 0.025);
+    assert_close(report.test_folds.accuracy.mean, 0.75);
+    assert_close(report.test_folds.accuracy.std, 0.);
+}
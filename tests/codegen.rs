@@ -0,0 +1,240 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use cgp::datasets::fitness_metric_type::FitnessFuncType;
+use cgp::global_params::CgpParameters;
+use cgp::utils::fitness_metrics::{fitness_categorical_multiclass, fitness_regression};
+use cgp::utils::utility_funcs::transpose;
+use cgp::utils::connection_sampling::ConnectionSampling;
+use cgp::utils::multiclass_decoding::MulticlassDecoding;
+use cgp::vanilla_cgp::chromosome::Chromosome;
+
+const NBR_INPUTS: usize = 6;
+const NBR_SAMPLES: usize = 300;
+const NBR_CHROMOSOMES: usize = 20;
+
+fn params(fitness_func_type: FitnessFuncType, nbr_outputs: usize, multiclass_decoding: MulticlassDecoding) -> CgpParameters {
+    return CgpParameters {
+        graph_width: 40,
+        rows: 1,
+        levels_back: None,
+        mu: 1,
+        lambda: 4,
+        eval_after_iterations: 0,
+        nbr_inputs: NBR_INPUTS,
+        nbr_outputs,
+        nbr_classes: 3,
+        fitness_func_type,
+        connection_sampling: ConnectionSampling::Uniform,
+        multiclass_decoding,
+        class_costs: None,
+    };
+}
+
+/// Samples in [-2, 2]; some values are (close to) zero to hit the protected division and logarithm.
+fn samples(rng: &mut ChaCha8Rng) -> Vec<Vec<f32>> {
+    return (0..NBR_SAMPLES)
+        .map(|_| (0..NBR_INPUTS)
+            .map(|_| match rng.gen_range(0..10) {
+                0 => 0.,
+                1 => rng.gen_range(-0.0002..0.0002),
+                _ => rng.gen_range(-2.0..2.0),
+            })
+            .collect())
+        .collect();
+}
+
+/// For `MulticlassDecoding::Thresholds`, the thresholds are learned on `samples` with random labels.
+fn chromosomes(rng: &mut ChaCha8Rng,
+               samples: &Vec<Vec<f32>>,
+               fitness_func_type: FitnessFuncType,
+               nbr_outputs: usize,
+               multiclass_decoding: MulticlassDecoding) -> Vec<Chromosome> {
+    let inputs = transpose(samples.clone());
+    let labels: Vec<usize> = (0..NBR_SAMPLES).map(|_| rng.gen_range(0..3)).collect();
+
+    return (0..NBR_CHROMOSOMES)
+        .map(|_| {
+            let mut chromosome = Chromosome::new(params(fitness_func_type, nbr_outputs, multiclass_decoding), rng);
+            for _ in 0..20 {
+                chromosome.mutate_prob(0.1, rng);
+            }
+            if multiclass_decoding == MulticlassDecoding::Thresholds {
+                chromosome.evaluate_incremental(&inputs, &labels);
+            }
+            chromosome
+        })
+        .collect();
+}
+
+fn work_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("cgp_codegen_{}_{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    return dir;
+}
+
+fn write_samples(dir: &Path, samples: &Vec<Vec<f32>>) -> PathBuf {
+    let path = dir.join("samples.txt");
+    let content: Vec<String> = samples
+        .iter()
+        .map(|sample| sample.iter().map(|x| format!("{:?}", x)).collect::<Vec<String>>().join(" "))
+        .collect();
+    fs::write(&path, content.join("\n")).unwrap();
+    return path;
+}
+
+/// Runs the program and parses one line of predictions per sample, one column per chromosome.
+fn run_program(binary: &Path, samples_path: &Path) -> Vec<Vec<usize>> {
+    let output = Command::new(binary).arg(samples_path).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let rows: Vec<Vec<usize>> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| line.split_whitespace().map(|p| p.parse().unwrap()).collect())
+        .collect();
+    return transpose(rows);
+}
+
+fn assert_matches_evaluate(chromosomes: &mut Vec<Chromosome>,
+                           samples: &Vec<Vec<f32>>,
+                           predictions: &Vec<Vec<usize>>,
+                           rng: &mut ChaCha8Rng) {
+    let inputs = transpose(samples.clone());
+    let nbr_classes = match chromosomes[0].params.fitness_func_type {
+        FitnessFuncType::ClassificationBinary => 2,
+        _ => 3,
+    };
+    let labels: Vec<usize> = (0..NBR_SAMPLES).map(|_| rng.gen_range(0..nbr_classes)).collect();
+
+    for (chromosome, predictions) in chromosomes.iter_mut().zip(predictions) {
+        assert_eq!(*predictions, chromosome.predict(samples));
+
+        let expected = chromosome.evaluate(&inputs, &labels);
+        let fitness = match chromosome.params.fitness_func_type {
+            FitnessFuncType::ClassificationMultiClass => fitness_categorical_multiclass(predictions, &labels),
+            _ => fitness_regression(predictions, &labels),
+        };
+        assert_eq!(fitness, expected);
+    }
+}
+
+fn rust_program(chromosomes: &Vec<Chromosome>) -> String {
+    let mut source = String::new();
+    for (i, chromosome) in chromosomes.iter().enumerate() {
+        source.push_str(&chromosome.to_rust_source(&format!("predict_{}", i)));
+    }
+    source.push_str("fn main() {\n");
+    source.push_str("    let path = std::env::args().nth(1).unwrap();\n");
+    source.push_str("    for line in std::fs::read_to_string(path).unwrap().lines() {\n");
+    source.push_str("        let x: Vec<f32> = line.split_whitespace().map(|v| v.parse().unwrap()).collect();\n");
+    source.push_str("        let predictions: Vec<String> = vec![");
+    for i in 0..chromosomes.len() {
+        source.push_str(&format!("predict_{}(&x).to_string(), ", i));
+    }
+    source.push_str("];\n");
+    source.push_str("        println!(\"{}\", predictions.join(\" \"));\n");
+    source.push_str("    }\n");
+    source.push_str("}\n");
+    return source;
+}
+
+fn c_program(chromosomes: &Vec<Chromosome>) -> String {
+    let mut source = String::from("#include <stdio.h>\n#include <stdlib.h>\n");
+    for (i, chromosome) in chromosomes.iter().enumerate() {
+        source.push_str(&chromosome.to_c_source(&format!("predict_{}", i)));
+    }
+    source.push_str("int main(int argc, char **argv) {\n");
+    source.push_str("    FILE *file = fopen(argv[1], \"r\");\n");
+    source.push_str(&format!("    float x[{}];\n", NBR_INPUTS));
+    source.push_str("    for (;;) {\n");
+    source.push_str(&format!("        for (int i = 0; i < {}; i++) {{\n", NBR_INPUTS));
+    source.push_str("            if (fscanf(file, \"%f\", &x[i]) != 1) { return 0; }\n");
+    source.push_str("        }\n");
+    for i in 0..chromosomes.len() {
+        source.push_str(&format!("        printf(\"%zu \", predict_{}(x));\n", i));
+    }
+    source.push_str("        printf(\"\\n\");\n");
+    source.push_str("    }\n");
+    source.push_str("}\n");
+    return source;
+}
+
+fn compile_rust(dir: &Path, source: &str) -> PathBuf {
+    let source_path = dir.join("program.rs");
+    let binary = dir.join("program_rs");
+    fs::write(&source_path, source).unwrap();
+
+    let rustc = std::env::var("RUSTC").unwrap_or("rustc".to_string());
+    let output = Command::new(rustc)
+        .args(["--edition", "2021", "-O", "-o"])
+        .arg(&binary)
+        .arg(&source_path)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    return binary;
+}
+
+/// None if no C compiler is installed.
+fn compile_c(dir: &Path, source: &str) -> Option<PathBuf> {
+    let source_path = dir.join("program.c");
+    let binary = dir.join("program_c");
+    fs::write(&source_path, source).unwrap();
+
+    let output = Command::new("cc")
+        .args(["-std=c99", "-O2", "-Wall", "-Werror", "-o"])
+        .arg(&binary)
+        .arg(&source_path)
+        .arg("-lm")
+        .output()
+        .ok()?;
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    return Some(binary);
+}
+
+#[test]
+fn generated_rust_matches_evaluate() {
+    let dir = work_dir("rust");
+    for (seed, fitness_func_type, nbr_outputs, multiclass_decoding) in [
+        (0, FitnessFuncType::ClassificationMultiClass, 3, MulticlassDecoding::Argmax),
+        (1, FitnessFuncType::ClassificationBinary, 1, MulticlassDecoding::Argmax),
+        (4, FitnessFuncType::ClassificationMultiClass, 1, MulticlassDecoding::Thresholds),
+    ] {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let samples = samples(&mut rng);
+        let mut chromosomes = chromosomes(&mut rng, &samples, fitness_func_type, nbr_outputs, multiclass_decoding);
+
+        let binary = compile_rust(&dir, &rust_program(&chromosomes));
+        let predictions = run_program(&binary, &write_samples(&dir, &samples));
+        assert_matches_evaluate(&mut chromosomes, &samples, &predictions, &mut rng);
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn generated_c_matches_evaluate() {
+    let dir = work_dir("c");
+    for (seed, fitness_func_type, nbr_outputs, multiclass_decoding) in [
+        (2, FitnessFuncType::ClassificationMultiClass, 3, MulticlassDecoding::Argmax),
+        (3, FitnessFuncType::ClassificationBinary, 1, MulticlassDecoding::Argmax),
+        (5, FitnessFuncType::ClassificationMultiClass, 1, MulticlassDecoding::Thresholds),
+    ] {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let samples = samples(&mut rng);
+        let mut chromosomes = chromosomes(&mut rng, &samples, fitness_func_type, nbr_outputs, multiclass_decoding);
+
+        let binary = match compile_c(&dir, &c_program(&chromosomes)) {
+            Some(binary) => binary,
+            None => {
+                eprintln!("no C compiler found, skipping");
+                return;
+            }
+        };
+        let predictions = run_program(&binary, &write_samples(&dir, &samples));
+        assert_matches_evaluate(&mut chromosomes, &samples, &predictions, &mut rng);
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,67 @@
+use serde_json::json;
+use cgp::analysis::compare_experiments::compare_to_baseline;
+use cgp::utils::experiment::{ExperimentGrid, RunConfig};
+use cgp::utils::mutation_strategy::MutationStrategy;
+use cgp::utils::run_logger::RunSummary;
+
+const GRID: &str = "
+data_bloat = [0.0, 0.2]
+redundancy_types = [1]
+nbr_nodes = [50]
+seeds = [7]
+
+[[datasets]]
+id = 3
+path = \"breast_cancer.data\"
+";
+
+fn summary(config: &RunConfig, fold: usize, fitness_eval: f32) -> RunSummary {
+    return serde_json::from_value(json!({
+        "run_id": config.run_id + fold,
+        "fold": fold,
+        "seed": config.seed,
+        "dataset": config.dataset_string(),
+        "data_bloat": config.data_bloat,
+        "redundancy_type": config.redundancy_type,
+        "nbr_nodes": config.nbr_nodes,
+        "iterations": 100,
+        "fitness_train": fitness_eval,
+        "fitness_eval": fitness_eval,
+        "stop_reason": "max_iterations",
+        "elapsed_seconds": 1.0,
+        "active_nodes": [],
+        "expressions": [],
+        "attribute_usage": {
+            "originals": 0, "copies": 0, "noisy_copies": 0, "noise": 0,
+            "sources_used_multiple_times": 0, "versions_per_source": {}
+        },
+        "redundancy_mapping": {},
+        "config": config,
+    })).unwrap();
+}
+
+#[test]
+fn arms_are_only_compared_with_the_baseline_of_the_same_settings() {
+    let grid: ExperimentGrid = toml::from_str(GRID).unwrap();
+    let (baseline, arm) = (grid.runs()[0].clone(), grid.runs()[1].clone());
+    let mut other_arm = arm.clone();
+    other_arm.mutation = MutationStrategy::Point { rate: 0.1, force_active: false };
+
+    let mut summaries: Vec<RunSummary> = vec![];
+    for fold in 0..5 {
+        summaries.push(summary(&baseline, fold, 0.1 + fold as f32 / 100.));
+        summaries.push(summary(&arm, fold, 0.2 + fold as f32 / 100.));
+        summaries.push(summary(&other_arm, fold, 0.3 + fold as f32 / 100.));
+    }
+
+    // the arm with point mutation has no baseline of its own
+    let comparisons = compare_to_baseline(&summaries);
+    assert_eq!(comparisons.len(), 2);
+    for comparison in &comparisons {
+        assert_eq!(comparison.settings, arm.settings_id());
+        assert_eq!((comparison.redundancy_type, comparison.data_bloat), (1, 20));
+        assert_eq!((comparison.n_baseline, comparison.n_arm, comparison.n_pairs), (5, 5, 5));
+    }
+    assert!((comparisons[0].median_arm - 0.22).abs() < 1e-6);
+    assert!((comparisons[0].median_baseline - 0.12).abs() < 1e-6);
+}
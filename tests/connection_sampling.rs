@@ -0,0 +1,157 @@
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use cgp::datasets::fitness_metric_type::FitnessFuncType;
+use cgp::global_params::CgpParameters;
+use cgp::utils::connection_sampling::ConnectionSampling;
+use cgp::utils::multiclass_decoding::MulticlassDecoding;
+use cgp::utils::mutation_strategy::MutationStrategy;
+use cgp::utils::node_type::NodeType;
+use cgp::vanilla_cgp::chromosome::Chromosome;
+
+const NBR_INPUTS: usize = 6;
+const GRAPH_WIDTH: usize = 40;
+const NBR_DRAWS: usize = 60_000;
+
+fn params(rows: usize, levels_back: Option<usize>, connection_sampling: ConnectionSampling) -> CgpParameters {
+    return CgpParameters {
+        graph_width: GRAPH_WIDTH,
+        rows,
+        levels_back,
+        mu: 1,
+        lambda: 4,
+        eval_after_iterations: 0,
+        nbr_inputs: NBR_INPUTS,
+        nbr_outputs: 2,
+        nbr_classes: 2,
+        fitness_func_type: FitnessFuncType::ClassificationMultiClass,
+        connection_sampling,
+        multiclass_decoding: MulticlassDecoding::Argmax,
+        class_costs: None,
+    };
+}
+
+/// Classical CGP connectivity: the nodes of the `levels_back` columns before the node, and the inputs
+/// only in the first `levels_back` columns.
+fn expected_range(position: usize, rows: usize, levels_back: Option<usize>) -> (usize, usize) {
+    let column = (position - NBR_INPUTS) / rows;
+    let highest = NBR_INPUTS + column * rows;
+    return match levels_back {
+        Some(levels_back) if column >= levels_back => (NBR_INPUTS + (column - levels_back) * rows, highest),
+        _ => (0, highest),
+    };
+}
+
+fn check_connections(chromosome: &Chromosome, rows: usize, levels_back: Option<usize>, connection_sampling: ConnectionSampling) {
+    for node in chromosome.nodes_grid.iter().filter(|node| node.node_type != NodeType::InputNode) {
+        let (lowest, highest) = node.connection_range();
+        let connections = match node.node_type {
+            NodeType::ComputationalNode => {
+                assert_eq!((lowest, highest), expected_range(node.position, rows, levels_back));
+                vec![node.connection0, node.connection1]
+            }
+            _ => {
+                assert_eq!((lowest, highest), (0, NBR_INPUTS + GRAPH_WIDTH));
+                vec![node.connection0]
+            }
+        };
+
+        for connection in connections {
+            assert!((lowest..highest).contains(&connection),
+                    "node {} connects to {} outside of {}..{}", node.position, connection, lowest, highest);
+            if let (&NodeType::ComputationalNode, ConnectionSampling::RecentNodes { window }) = (&node.node_type, connection_sampling) {
+                assert!(connection < NBR_INPUTS || connection + window >= highest,
+                        "node {} connects to {} outside of its window", node.position, connection);
+            }
+        }
+    }
+}
+
+#[test]
+fn connections_stay_in_the_connection_range() {
+    let grids = [(1, None), (1, Some(3)), (4, Some(2)), (5, None), (8, Some(1))];
+    let samplings = [
+        ConnectionSampling::Uniform,
+        ConnectionSampling::RecentNodes { window: 5 },
+        ConnectionSampling::InputSplit { input_probability: 0.3 },
+    ];
+    let strategies = [
+        MutationStrategy::SingleActive,
+        MutationStrategy::Point { rate: 0.1, force_active: false },
+        MutationStrategy::Genewise { connection_rate: 0.2, function_rate: 0.05, force_active: true },
+    ];
+
+    let mut rng = ChaCha8Rng::seed_from_u64(0);
+    for (rows, levels_back) in grids {
+        for connection_sampling in samplings {
+            for strategy in strategies {
+                let mut chromosome = Chromosome::new(params(rows, levels_back, connection_sampling), &mut rng);
+                check_connections(&chromosome, rows, levels_back, connection_sampling);
+
+                for _ in 0..100 {
+                    // the mutations expect the active nodes of the last evaluation
+                    chromosome.get_active_nodes_id();
+                    strategy.mutate(&mut chromosome, &mut rng);
+                    check_connections(&chromosome, rows, levels_back, connection_sampling);
+                }
+            }
+        }
+    }
+}
+
+/// Relative frequency of every node id among `NBR_DRAWS` samples.
+fn frequencies(sampling: ConnectionSampling, lowest: usize, highest: usize, nbr_inputs: usize, current: Option<usize>) -> Vec<f64> {
+    let mut rng = ChaCha8Rng::seed_from_u64(1);
+    let mut counts = vec![0usize; highest];
+    for _ in 0..NBR_DRAWS {
+        counts[sampling.sample(lowest, highest, nbr_inputs, current, &mut rng)] += 1;
+    }
+    return counts.iter().map(|count| *count as f64 / NBR_DRAWS as f64).collect();
+}
+
+/// Compares with the expected probabilities, within 10% of each.
+fn assert_distribution(frequencies: &Vec<f64>, expected: &Vec<f64>) {
+    for (node_id, (frequency, probability)) in frequencies.iter().zip(expected.iter()).enumerate() {
+        assert!((frequency - probability).abs() <= 0.1 * probability,
+                "node {}: frequency {} instead of {}", node_id, frequency, probability);
+    }
+}
+
+#[test]
+fn uniform_sampling_draws_every_node_equally_often() {
+    assert_distribution(&frequencies(ConnectionSampling::Uniform, 0, 10, 4, None), &vec![0.1; 10]);
+
+    // a mutated connection never keeps its value
+    let mut expected = vec![0.; 10];
+    expected[6..10].iter_mut().for_each(|p| *p = 1. / 3.);
+    expected[7] = 0.;
+    assert_distribution(&frequencies(ConnectionSampling::Uniform, 6, 10, 4, Some(7)), &expected);
+}
+
+#[test]
+fn recent_nodes_sampling_draws_inputs_and_the_window_equally_often() {
+    // inputs 0..5 and the computational nodes 17, 18 and 19
+    let mut expected = vec![0.; 20];
+    for node_id in (0..5).chain(17..20) {
+        expected[node_id] = 1. / 8.;
+    }
+    assert_distribution(&frequencies(ConnectionSampling::RecentNodes { window: 3 }, 0, 20, 5, None), &expected);
+
+    expected[2] = 0.;
+    for node_id in (0..2).chain(3..5).chain(17..20) {
+        expected[node_id] = 1. / 7.;
+    }
+    assert_distribution(&frequencies(ConnectionSampling::RecentNodes { window: 3 }, 0, 20, 5, Some(2)), &expected);
+}
+
+#[test]
+fn input_split_sampling_draws_inputs_with_the_given_probability() {
+    // inputs 0..5 share 0.25, the computational nodes 5..20 share 0.75
+    let mut expected = vec![0.05; 20];
+    let frequencies_split = frequencies(ConnectionSampling::InputSplit { input_probability: 0.25 }, 0, 20, 5, None);
+    assert_distribution(&frequencies_split, &expected);
+
+    // if the grid excludes the inputs, all draws go to the computational nodes
+    expected = vec![0.; 20];
+    expected[8..14].iter_mut().for_each(|p| *p = 1. / 6.);
+    assert_distribution(&frequencies(ConnectionSampling::InputSplit { input_probability: 0.25 }, 8, 14, 5, None), &expected);
+}
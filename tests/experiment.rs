@@ -0,0 +1,80 @@
+use cgp::utils::experiment::{ExperimentGrid, RunConfig};
+use cgp::utils::mutation_strategy::{MutationStrategy, RateControl};
+
+fn run_config(grid: &str) -> RunConfig {
+    let grid: ExperimentGrid = toml::from_str(grid).unwrap();
+    return grid.runs().remove(0);
+}
+
+const GRID: &str = "
+data_bloat = [0.2]
+redundancy_types = [1]
+nbr_nodes = [50]
+seeds = [7]
+
+[[datasets]]
+id = 3
+path = \"breast_cancer.data\"
+";
+
+#[test]
+fn logging_settings_are_not_compared() {
+    let config = run_config(GRID);
+    let mut other = config.clone();
+    other.resume = !other.resume;
+    other.checkpoint_interval += 1;
+    other.log_genomes += 1;
+    other.eval_after_iterations += 1;
+    other.track_input_usage += 1;
+    assert!(config.differing_settings(&other).is_empty());
+
+    // a config read back from a summary
+    let stored: RunConfig = serde_json::from_str(&serde_json::to_string(&config).unwrap()).unwrap();
+    assert!(config.differing_settings(&stored).is_empty());
+}
+
+#[test]
+fn reports_differing_settings() {
+    let config = run_config(GRID);
+    let mut other = config.clone();
+    other.seed += 1;
+    other.mutation = MutationStrategy::Point { rate: 0.1, force_active: false };
+    other.stopping_criteria.max_iterations = Some(10);
+
+    let mut differing = config.differing_settings(&other);
+    differing.sort();
+    assert_eq!(differing, vec!["mutation", "seed", "stopping_criteria"]);
+}
+
+#[test]
+#[should_panic(expected = "needs point or genewise mutation")]
+fn grid_rate_control_needs_a_mutation_rate() {
+    let config = run_config(&format!("{}\n[rate_control]\ntype = \"linear\"\nfinal_rate = 0.01\niterations = 100\n", GRID));
+    assert!(matches!(config.rate_control, RateControl::Linear { .. }));
+    config.validate();
+}
+
+#[test]
+fn arms_of_an_experiment_share_their_settings_id() {
+    let config = run_config(GRID);
+    let mut arm = config.clone();
+    arm.seed += 1;
+    arm.run_id += 5;
+    arm.data_bloat = 0.0;
+    arm.redundancy_type = 0;
+    arm.dataset_path = "other.data".to_string();
+    assert_eq!(config.settings_id(), arm.settings_id());
+
+    let mut other = config.clone();
+    other.mutation = MutationStrategy::Point { rate: 0.1, force_active: false };
+    assert_ne!(config.settings_id(), other.settings_id());
+    assert_ne!(config.save_path(), other.save_path());
+    assert_eq!(config.save_path().parent(), other.save_path().parent());
+}
+
+#[test]
+#[should_panic(expected = "more than once")]
+fn grid_runs_must_not_share_their_output() {
+    let grid: ExperimentGrid = toml::from_str(&GRID.replace("data_bloat = [0.2]", "data_bloat = [0.2, 0.201]")).unwrap();
+    grid.validate();
+}
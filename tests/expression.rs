@@ -0,0 +1,89 @@
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use cgp::datasets::fitness_metric_type::FitnessFuncType;
+use cgp::global_params::CgpParameters;
+use cgp::utils::connection_sampling::ConnectionSampling;
+use cgp::utils::multiclass_decoding::MulticlassDecoding;
+use cgp::vanilla_cgp::chromosome::Chromosome;
+
+const NBR_INPUTS: usize = 2;
+
+/// Chromosome whose computational nodes are `(function id, connection0, connection1)`, in order,
+/// with a single output connected to the last of them.
+fn chromosome(nodes: &[(usize, usize, usize)]) -> Chromosome {
+    let params = CgpParameters {
+        graph_width: nodes.len(),
+        rows: 1,
+        levels_back: None,
+        mu: 1,
+        lambda: 4,
+        eval_after_iterations: 0,
+        nbr_inputs: NBR_INPUTS,
+        nbr_outputs: 1,
+        nbr_classes: 0,
+        fitness_func_type: FitnessFuncType::Regression,
+        connection_sampling: ConnectionSampling::Uniform,
+        multiclass_decoding: MulticlassDecoding::Argmax,
+        class_costs: None,
+    };
+    let mut chromosome = Chromosome::new(params, &mut ChaCha8Rng::seed_from_u64(0));
+
+    for (i, (function_id, connection0, connection1)) in nodes.iter().enumerate() {
+        let node = &mut chromosome.nodes_grid[NBR_INPUTS + i];
+        node.function_id = *function_id;
+        node.connection0 = *connection0;
+        node.connection1 = *connection1;
+    }
+    let output_node_id = chromosome.output_node_ids[0];
+    chromosome.nodes_grid[output_node_id].connection0 = NBR_INPUTS + nodes.len() - 1;
+    return chromosome;
+}
+
+#[test]
+fn shared_nodes_become_temporaries() {
+    // n2 = x0 * x1, n3 = sin(n2), n4 = n2 + n3
+    let chromosome = chromosome(&[(2, 0, 1), (4, 2, 2), (0, 2, 3)]);
+    assert_eq!(chromosome.to_expressions(false, false), vec!["let n2 = x0 * x1; n2 + sin(n2)"]);
+}
+
+#[test]
+fn expression_size_is_linear_in_reuse_depth() {
+    // every node adds its predecessor to itself, expanding the graph would double the size at each node
+    let nodes: Vec<(usize, usize, usize)> = (0..500)
+        .map(|i| match i {
+            0 => (0, 0, 1),
+            _ => (0, NBR_INPUTS + i - 1, NBR_INPUTS + i - 1),
+        })
+        .collect();
+    let chromosome = chromosome(&nodes);
+
+    let expressions = chromosome.to_expressions(true, false);
+    assert!(expressions[0].len() < 500 * 40, "{} characters", expressions[0].len());
+    assert!(expressions[0].ends_with("; n500 + n500"), "{}", expressions[0]);
+}
+
+#[test]
+fn differences_of_equal_subexpressions_are_kept_by_default() {
+    // exp(exp(x0)) can be infinite, then n - n is NaN and not 0
+    let difference = chromosome(&[(7, 0, 0), (7, 2, 2), (1, 3, 3)]);
+    assert_eq!(difference.to_expressions(true, false), vec!["let n3 = exp(exp(x0)); n3 - n3"]);
+
+    let quotient = chromosome(&[(7, 0, 0), (7, 2, 2), (3, 3, 3)]);
+    assert_eq!(quotient.to_expressions(true, false), vec!["let n3 = exp(exp(x0)); n3 / n3"]);
+}
+
+#[test]
+fn differences_of_equal_subexpressions_are_simplified_assuming_finite_values() {
+    // n3 = sin(x0) - sin(x0), n4 = x1 / x1, n5 = n3 + n4
+    let sum = chromosome(&[(4, 0, 0), (4, 0, 0), (1, 2, 3), (3, 1, 1), (0, 4, 5)]);
+    assert_eq!(sum.to_expressions(true, false), vec!["sin(x0) - sin(x0) + x1 / x1"]);
+    assert_eq!(sum.to_expressions(true, true), vec!["1"]);
+
+    // only equal operands are simplified
+    let different = chromosome(&[(4, 0, 0), (5, 0, 0), (1, 2, 3)]);
+    assert_eq!(different.to_expressions(true, true), vec!["sin(x0) - cos(x0)"]);
+
+    // with shared nodes, equal temporaries are simplified and unused ones dropped
+    let difference = chromosome(&[(7, 0, 0), (7, 2, 2), (1, 3, 3)]);
+    assert_eq!(difference.to_expressions(true, true), vec!["0"]);
+}
@@ -0,0 +1,82 @@
+use std::fs;
+use std::fs::File;
+use std::io::Read;
+use std::path::PathBuf;
+use flate2::read::MultiGzDecoder;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use cgp::datasets::fitness_metric_type::FitnessFuncType;
+use cgp::global_params::CgpParameters;
+use cgp::utils::connection_sampling::ConnectionSampling;
+use cgp::utils::logging_stuff::LoggingStuff;
+use cgp::utils::multiclass_decoding::MulticlassDecoding;
+use cgp::vanilla_cgp::chromosome::Chromosome;
+
+fn chromosome() -> Chromosome {
+    let params = CgpParameters {
+        graph_width: 10,
+        rows: 1,
+        levels_back: None,
+        mu: 1,
+        lambda: 4,
+        eval_after_iterations: 0,
+        nbr_inputs: 3,
+        nbr_outputs: 1,
+        nbr_classes: 2,
+        fitness_func_type: FitnessFuncType::ClassificationBinary,
+        connection_sampling: ConnectionSampling::Uniform,
+        multiclass_decoding: MulticlassDecoding::Argmax,
+        class_costs: None,
+    };
+    return Chromosome::new(params, &mut ChaCha8Rng::seed_from_u64(0));
+}
+
+fn log_path(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("cgp_genome_log_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    return dir.join(name);
+}
+
+fn iterations(path: &PathBuf) -> Vec<usize> {
+    let mut contents = String::new();
+    MultiGzDecoder::new(File::open(path).unwrap()).read_to_string(&mut contents).unwrap();
+    return contents
+        .lines()
+        .map(|line| line.strip_prefix("Iteration: ").unwrap().split(';').next().unwrap().parse().unwrap())
+        .collect();
+}
+
+#[test]
+fn closed_log_is_complete() {
+    let path = log_path("closed.txt.gz");
+    let chromosome = chromosome();
+
+    let mut logger = LoggingStuff::new(path.clone());
+    for it in 0..3 {
+        logger.execute(it, &chromosome);
+    }
+    let len = logger.checkpoint();
+    assert_eq!(len, fs::metadata(&path).unwrap().len());
+    logger.execute(3, &chromosome);
+    logger.close_writer().unwrap();
+
+    assert_eq!(iterations(&path), vec![0, 1, 2, 3]);
+}
+
+#[test]
+fn resumed_log_drops_the_records_after_the_checkpoint() {
+    let path = log_path("resumed.txt.gz");
+    let chromosome = chromosome();
+
+    let mut logger = LoggingStuff::new(path.clone());
+    logger.execute(0, &chromosome);
+    let len = logger.checkpoint();
+    logger.execute(1, &chromosome);
+    logger.close_writer().unwrap();
+
+    let mut logger = LoggingStuff::resume(path.clone(), len);
+    logger.execute(5, &chromosome);
+    logger.close_writer().unwrap();
+
+    assert_eq!(iterations(&path), vec![0, 5]);
+}
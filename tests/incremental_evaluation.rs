@@ -0,0 +1,100 @@
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use cgp::datasets::fitness_metric_type::FitnessFuncType;
+use cgp::global_params::CgpParameters;
+use cgp::utils::connection_sampling::ConnectionSampling;
+use cgp::utils::multiclass_decoding::MulticlassDecoding;
+use cgp::utils::mutation_strategy::MutationStrategy;
+use cgp::utils::utility_funcs::transpose;
+use cgp::vanilla_cgp::chromosome::Chromosome;
+
+const NBR_INPUTS: usize = 4;
+const NBR_SAMPLES: usize = 40;
+const NBR_MUTATIONS: usize = 300;
+
+fn params(fitness_func_type: FitnessFuncType, nbr_outputs: usize, nbr_classes: usize) -> CgpParameters {
+    return CgpParameters {
+        graph_width: 30,
+        rows: 1,
+        levels_back: None,
+        mu: 1,
+        lambda: 4,
+        eval_after_iterations: 0,
+        nbr_inputs: NBR_INPUTS,
+        nbr_outputs,
+        nbr_classes,
+        fitness_func_type,
+        connection_sampling: ConnectionSampling::Uniform,
+        multiclass_decoding: MulticlassDecoding::Argmax,
+        class_costs: None,
+    };
+}
+
+fn strategies() -> Vec<MutationStrategy> {
+    return vec![
+        MutationStrategy::SingleActive,
+        MutationStrategy::Point { rate: 0.05, force_active: false },
+        MutationStrategy::Point { rate: 0.05, force_active: true },
+        MutationStrategy::Genewise { connection_rate: 0.1, function_rate: 0.02, force_active: false },
+        MutationStrategy::Genewise { connection_rate: 0.02, function_rate: 0.1, force_active: true },
+    ];
+}
+
+/// Bit patterns of the output node values cached by the last `evaluate_incremental`, one row per output.
+fn cached_output_bits(chromosome: &Chromosome) -> Vec<Vec<u32>> {
+    return chromosome.output_node_ids
+        .iter()
+        .map(|id| chromosome.cached_outputs[id].iter().map(|x| x.to_bits()).collect())
+        .collect();
+}
+
+/// Bit patterns of the output node values of a full evaluation, one row per output.
+fn full_output_bits(chromosome: &Chromosome, samples: &Vec<Vec<f32>>) -> Vec<Vec<u32>> {
+    return transpose(chromosome.predict_raw(samples))
+        .iter()
+        .map(|output| output.iter().map(|x| x.to_bits()).collect())
+        .collect();
+}
+
+/// Mutates a lineage of chromosomes as the runner does: offspring are clones of the parent, including its
+/// cached outputs, and replace it at random. Every offspring must evaluate to the same outputs and fitness
+/// incrementally and from scratch.
+fn check_lineage(fitness_func_type: FitnessFuncType, nbr_outputs: usize, nbr_classes: usize, seed: u64) {
+    for strategy in strategies() {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let samples: Vec<Vec<f32>> = (0..NBR_SAMPLES)
+            .map(|_| (0..NBR_INPUTS).map(|_| rng.gen_range(-2.0..2.0)).collect())
+            .collect();
+        let labels: Vec<usize> = (0..NBR_SAMPLES).map(|_| rng.gen_range(0..nbr_classes)).collect();
+        let inputs = transpose(samples.clone());
+
+        let mut parent = Chromosome::new(params(fitness_func_type, nbr_outputs, nbr_classes), &mut rng);
+        parent.evaluate_incremental(&inputs, &labels);
+
+        for iteration in 0..NBR_MUTATIONS {
+            let mut child = parent.clone();
+            strategy.mutate(&mut child, &mut rng);
+
+            let incremental_fitness = child.evaluate_incremental(&inputs, &labels);
+            let full_fitness = child.clone().evaluate(&inputs, &labels);
+            assert_eq!(incremental_fitness.to_bits(), full_fitness.to_bits(),
+                       "{:?}, iteration {}: {} != {}", strategy, iteration, incremental_fitness, full_fitness);
+            assert_eq!(cached_output_bits(&child), full_output_bits(&child, &samples),
+                       "{:?}, iteration {}", strategy, iteration);
+
+            if rng.gen_bool(0.5) {
+                parent = child;
+            }
+        }
+    }
+}
+
+#[test]
+fn incremental_evaluation_matches_full_evaluation_binary() {
+    check_lineage(FitnessFuncType::ClassificationBinary, 1, 2, 0);
+}
+
+#[test]
+fn incremental_evaluation_matches_full_evaluation_multiclass() {
+    check_lineage(FitnessFuncType::ClassificationMultiClass, 3, 3, 1);
+}
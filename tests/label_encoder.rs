@@ -0,0 +1,30 @@
+use cgp::datasets::dataset_utils::{unseen_labels, LabelEncoder};
+
+#[test]
+fn numeric_labels_are_compared_by_value() {
+    let label_encoder = LabelEncoder::fit(&["2", "1.0", "10", "1"]);
+    assert!(label_encoder.numeric);
+    assert_eq!(label_encoder.classes, ["1", "2", "10"]);
+    assert_eq!(label_encoder.transform(&["1.0", " 1", "10.00", "2"]), vec![0, 0, 2, 1]);
+}
+
+#[test]
+fn mixed_labels_are_compared_as_text() {
+    let label_encoder = LabelEncoder::fit(&["1.0", "x", "1"]);
+    assert!(!label_encoder.numeric);
+    assert_eq!(label_encoder.classes, ["1", "1.0", "x"]);
+    assert_eq!(label_encoder.transform(&["1.0", "x", "1"]), vec![1, 2, 0]);
+    assert_eq!(label_encoder.encode("1.00"), None);
+}
+
+#[test]
+#[should_panic(expected = "test label 3 does not occur")]
+fn rejects_unseen_test_labels() {
+    LabelEncoder::fit_train_test(&["1", "2"], &["2", "3"]);
+}
+
+#[test]
+fn finds_unseen_labels() {
+    assert_eq!(unseen_labels(&vec![0, 1, 1], &vec![2, 1, 3, 2]), vec![2, 3]);
+    assert!(unseen_labels(&vec![0, 1, 2], &vec![2, 0]).is_empty());
+}
@@ -0,0 +1,95 @@
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use cgp::analysis::attribute_usage::AttributeLabels;
+use cgp::datasets::dataset_utils::{add_redundancies, LabelEncoder, RedundancyType, Scaler};
+use cgp::datasets::fitness_metric_type::FitnessFuncType;
+use cgp::global_params::CgpParameters;
+use cgp::utils::connection_sampling::ConnectionSampling;
+use cgp::utils::model::Model;
+use cgp::utils::multiclass_decoding::MulticlassDecoding;
+use cgp::utils::mutation_strategy::MutationStrategy;
+use cgp::utils::utility_funcs::transpose;
+use cgp::vanilla_cgp::chromosome::Chromosome;
+
+const NBR_ATTRIBUTES: usize = 4;
+const NBR_SAMPLES: usize = 40;
+
+fn params(nbr_inputs: usize) -> CgpParameters {
+    return CgpParameters {
+        graph_width: 30,
+        rows: 1,
+        levels_back: None,
+        mu: 1,
+        lambda: 4,
+        eval_after_iterations: 0,
+        nbr_inputs,
+        nbr_outputs: 1,
+        nbr_classes: 2,
+        fitness_func_type: FitnessFuncType::ClassificationBinary,
+        connection_sampling: ConnectionSampling::Uniform,
+        multiclass_decoding: MulticlassDecoding::Argmax,
+        class_costs: None,
+    };
+}
+
+/// Trains a chromosome on raw data with a bloat of `redundancy_type` as a run does and returns its model,
+/// the raw samples and the chromosome's predictions on the bloated training inputs.
+fn train(redundancy_type: RedundancyType, seed: u64) -> (Model, Vec<Vec<f32>>, Vec<usize>) {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let samples: Vec<Vec<f32>> = (0..NBR_SAMPLES)
+        .map(|_| (0..NBR_ATTRIBUTES).map(|_| rng.gen_range(0.0..10.0)).collect())
+        .collect();
+    let labels: Vec<usize> = samples.iter().map(|sample| (sample[0] + sample[2] > 10.) as usize).collect();
+
+    let scaler = Scaler::fit(&samples);
+    let (train_data, _, mapping) = add_redundancies(scaler.transform(samples.clone()), None, 1.0, redundancy_type, &mut rng);
+    let inputs = transpose(train_data.clone());
+
+    let mut parent = Chromosome::new(params(train_data[0].len()), &mut rng);
+    let mut parent_fitness = parent.evaluate(&inputs, &labels);
+    for _ in 0..200 {
+        let mut child = parent.clone();
+        MutationStrategy::SingleActive.mutate(&mut child, &mut rng);
+        let child_fitness = child.evaluate(&inputs, &labels);
+        if child_fitness <= parent_fitness {
+            (parent, parent_fitness) = (child, child_fitness);
+        }
+    }
+
+    let predictions = parent.predict(&train_data);
+    let input_layout = AttributeLabels::new(train_data[0].len(), &mapping, redundancy_type);
+    let model = Model::new(parent, scaler, input_layout, Some(LabelEncoder::fit(&["benign", "malignant"])));
+    return (model, samples, predictions);
+}
+
+/// Saves and loads the model in a temporary file.
+fn round_trip(model: &Model, name: &str) -> Model {
+    let path = std::env::temp_dir().join(format!("cgp_model_{}_{}.json", std::process::id(), name));
+    model.save(&path);
+    let loaded = Model::load(&path);
+    std::fs::remove_file(&path).unwrap();
+    return loaded;
+}
+
+#[test]
+fn loaded_model_predicts_as_the_chromosome() {
+    for seed in 0..5 {
+        let (model, samples, predictions) = train(RedundancyType::Copy, seed);
+        let loaded = round_trip(&model, "copy");
+
+        // copies carry no noise, so the rebuilt inputs equal the training inputs
+        assert_eq!(loaded.predict(samples, &mut ChaCha8Rng::seed_from_u64(0)), predictions, "seed {}", seed);
+        assert_eq!(loaded.label_encoder.unwrap().classes, ["benign", "malignant"]);
+    }
+}
+
+#[test]
+fn loaded_model_redraws_noise_from_the_seed() {
+    for redundancy_type in [RedundancyType::NoisyCopy, RedundancyType::Noise] {
+        let (model, samples, _) = train(redundancy_type, 0);
+        let loaded = round_trip(&model, "noise");
+
+        assert_eq!(loaded.predict(samples.clone(), &mut ChaCha8Rng::seed_from_u64(3)),
+                   model.predict(samples, &mut ChaCha8Rng::seed_from_u64(3)));
+    }
+}
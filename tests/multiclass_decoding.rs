@@ -0,0 +1,62 @@
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use cgp::datasets::fitness_metric_type::FitnessFuncType;
+use cgp::global_params::CgpParameters;
+use cgp::utils::connection_sampling::ConnectionSampling;
+use cgp::utils::multiclass_decoding::{ClassThresholds, MulticlassDecoding};
+use cgp::utils::utility_funcs::transpose;
+use cgp::vanilla_cgp::chromosome::Chromosome;
+
+#[test]
+fn thresholds_separate_class_means() {
+    let thresholds = ClassThresholds::fit(&[4., 6., 0., 2., 10., 10.], &[1, 1, 0, 0, 2, 2], 3);
+    assert_eq!(thresholds.class_order, vec![0, 1, 2]);
+    assert_eq!(thresholds.thresholds, vec![3., 7.5]);
+    assert_eq!((thresholds.predict(-1.), thresholds.predict(3.), thresholds.predict(8.)), (0, 1, 2));
+}
+
+#[test]
+fn classes_without_finite_outputs_get_finite_thresholds() {
+    // class 1 only has non-finite outputs and class 3 does not occur at all
+    let outputs = [-2., 0., f32::NAN, f32::INFINITY, -4., f32::NEG_INFINITY];
+    let thresholds = ClassThresholds::fit(&outputs, &[0, 0, 1, 1, 2, 2], 4);
+    assert_eq!(thresholds.class_order[..2], [2, 0]);
+    assert_eq!(thresholds.thresholds.len(), 3);
+    assert!(thresholds.thresholds.iter().all(|threshold| threshold.is_finite()), "{:?}", thresholds.thresholds);
+    assert!(thresholds.thresholds.windows(2).all(|pair| pair[0] <= pair[1]));
+
+    let restored: ClassThresholds = serde_json::from_str(&serde_json::to_string(&thresholds).unwrap()).unwrap();
+    assert_eq!(restored, thresholds);
+}
+
+#[test]
+fn thresholds_of_a_chromosome_survive_a_round_trip() {
+    let mut rng = ChaCha8Rng::seed_from_u64(3);
+    let params = CgpParameters {
+        graph_width: 20,
+        rows: 1,
+        levels_back: None,
+        mu: 1,
+        lambda: 4,
+        eval_after_iterations: 0,
+        nbr_inputs: 2,
+        nbr_outputs: 1,
+        nbr_classes: 4,
+        fitness_func_type: FitnessFuncType::ClassificationMultiClass,
+        connection_sampling: ConnectionSampling::Uniform,
+        multiclass_decoding: MulticlassDecoding::Thresholds,
+        class_costs: None,
+    };
+    let samples: Vec<Vec<f32>> = (0..30).map(|_| vec![rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0)]).collect();
+    // the last class of the label encoder is missing in the training labels
+    let labels: Vec<usize> = (0..30).map(|_| rng.gen_range(0..3)).collect();
+
+    let mut chromosome = Chromosome::new(params, &mut rng);
+    chromosome.evaluate_incremental(&transpose(samples.clone()), &labels);
+    let thresholds = chromosome.class_thresholds.clone().unwrap();
+    assert_eq!(thresholds.class_order.len(), 4);
+
+    let restored: Chromosome = serde_json::from_str(&serde_json::to_string(&chromosome).unwrap()).unwrap();
+    assert_eq!(restored.class_thresholds, Some(thresholds));
+    assert_eq!(restored.predict(&samples), chromosome.predict(&samples));
+}
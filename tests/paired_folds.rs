@@ -0,0 +1,56 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use serde_json::Value;
+
+const DATASET: &str = "src/datasets/data/breast+cancer+wisconsin+diagnostic.data";
+const NBR_FOLDS: usize = 5;
+
+/// Runs the binary in an empty directory and returns the class supports of the train and test data of every fold.
+fn fold_supports(name: &str, extra_args: &[&str], arm_path: &str) -> Vec<(Value, Value)> {
+    let dir = std::env::temp_dir().join(format!("cgp_paired_folds_{}_{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    let dataset_path = Path::new(env!("CARGO_MANIFEST_DIR")).join(DATASET);
+    let output = Command::new(env!("CARGO_BIN_EXE_cgp"))
+        .current_dir(&dir)
+        .args(["--dataset", "3", "--nbr-nodes", "20", "--seed", "5", "--max-iterations", "20", "--threads", "1"])
+        .arg("--dataset-path")
+        .arg(&dataset_path)
+        .args(extra_args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let save_path = fs::read_dir(dir.join("Experiments_Output/breast_cancer").join(arm_path).join("number_nodes_20"))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap()
+        .path();
+    let supports = |report: &Value| -> Value {
+        return report["classes"].as_array().unwrap().iter().map(|class| class["support"].clone()).collect();
+    };
+    let fold_supports = (0..NBR_FOLDS)
+        .map(|run_id| {
+            let contents = fs::read_to_string(save_path.join(format!("run_{}_report.json", run_id))).unwrap();
+            let report: Value = serde_json::from_str(&contents).unwrap();
+            (supports(&report["train"]), supports(&report["test"]))
+        })
+        .collect();
+    fs::remove_dir_all(&dir).unwrap();
+    return fold_supports;
+}
+
+/// The Wilcoxon test of `analyze` pairs runs by seed and fold, so the redundancy must not change the folds.
+#[test]
+fn baseline_and_arms_use_the_same_folds() {
+    let baseline = fold_supports("baseline", &["--data-bloat", "0.0"], "baseline");
+    for redundancy_type in ["0", "1", "2"] {
+        let arm = fold_supports(redundancy_type,
+                                &["--data-bloat", "0.5", "--redundancy-type", redundancy_type],
+                                &format!("redundancy_type_{}/databloat_50", redundancy_type));
+        assert_eq!(arm, baseline, "redundancy type {}", redundancy_type);
+    }
+}
@@ -0,0 +1,88 @@
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use cgp::datasets::dataset_utils::{add_redundancies, RedundancyType};
+use cgp::utils::utility_funcs::transpose;
+
+const NBR_ATTRIBUTES: usize = 6;
+const NBR_SAMPLES: usize = 20;
+
+/// Row-major data whose values are distinct, so every column is only equal to itself.
+fn data(rng: &mut ChaCha8Rng) -> Vec<Vec<f32>> {
+    return (0..NBR_SAMPLES)
+        .map(|_| (0..NBR_ATTRIBUTES).map(|_| rng.gen_range(-10.0..10.0)).collect())
+        .collect();
+}
+
+/// Original and bloated train and test columns with the mapping of `add_redundancies`.
+fn bloat(seed: u64, r_type: RedundancyType) -> ([Vec<Vec<f32>>; 4], Vec<(usize, usize)>) {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let train_data = data(&mut rng);
+    let test_data = data(&mut rng);
+
+    let (new_train_data, new_test_data, mapping) = add_redundancies(train_data.clone(),
+                                                                    Some(test_data.clone()),
+                                                                    2.0,
+                                                                    r_type,
+                                                                    &mut rng);
+    let mut pairs: Vec<(usize, usize)> = mapping
+        .iter()
+        .flat_map(|(source, input_ids)| input_ids.iter().map(|input_id| (*source, *input_id)))
+        .collect();
+    pairs.sort();
+
+    let columns = [transpose(train_data), transpose(new_train_data), transpose(test_data), transpose(new_test_data.unwrap())];
+    return (columns, pairs);
+}
+
+#[test]
+fn copies_are_mapped_to_their_source() {
+    for seed in 0..20 {
+        let ([columns, new_columns, test_columns, new_test_columns], pairs) = bloat(seed, RedundancyType::Copy);
+        for (source, copy_id) in pairs {
+            assert_eq!(new_columns[copy_id], columns[source], "seed {}: input {} is not a copy of {}", seed, copy_id, source);
+            assert_eq!(new_test_columns[copy_id], test_columns[source], "seed {}: input {} is not a copy of {}", seed, copy_id, source);
+        }
+    }
+}
+
+#[test]
+fn noisy_copies_are_mapped_to_their_source() {
+    // every value of a noisy copy is the source value with up to 10% noise
+    let is_noisy_copy = |copy: &Vec<f32>, source: &Vec<f32>| copy
+        .iter()
+        .zip(source.iter())
+        .all(|(x, y)| (x - y).abs() <= 0.1 * y.abs() + 1e-6);
+
+    for seed in 0..20 {
+        let ([columns, new_columns, test_columns, new_test_columns], pairs) = bloat(seed, RedundancyType::NoisyCopy);
+        for (source, copy_id) in pairs {
+            assert!(is_noisy_copy(&new_columns[copy_id], &columns[source]), "seed {}: input {} is not a noisy copy of {}", seed, copy_id, source);
+            assert!(is_noisy_copy(&new_test_columns[copy_id], &test_columns[source]), "seed {}: input {} is not a noisy copy of {}", seed, copy_id, source);
+        }
+    }
+}
+
+#[test]
+fn mapped_inputs_are_the_added_columns() {
+    for r_type in [RedundancyType::Copy, RedundancyType::NoisyCopy, RedundancyType::Noise] {
+        for seed in 0..20 {
+            let ([columns, new_columns, test_columns, new_test_columns], pairs) = bloat(seed, r_type);
+            let mut added: Vec<usize> = pairs.iter().map(|(_, input_id)| *input_id).collect();
+            added.sort();
+            added.dedup();
+            assert_eq!(added.len(), new_columns.len() - NBR_ATTRIBUTES, "seed {}", seed);
+
+            // without the added columns, the original attributes are left in their order
+            let remaining = |new_columns: &Vec<Vec<f32>>| -> Vec<Vec<f32>> {
+                return new_columns
+                    .iter()
+                    .enumerate()
+                    .filter(|(input_id, _)| !added.contains(input_id))
+                    .map(|(_, column)| column.clone())
+                    .collect();
+            };
+            assert_eq!(remaining(&new_columns), columns, "seed {}", seed);
+            assert_eq!(remaining(&new_test_columns), test_columns, "seed {}", seed);
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use cgp::utils::selection::{crowding_distances, non_dominated_sort, nsga2_survivors, Objectives};
+
+fn objectives(values: &[(f32, usize, usize)]) -> Vec<Objectives> {
+    return values
+        .iter()
+        .map(|(error, active_nodes, active_inputs)| Objectives {
+            error: *error,
+            active_nodes: *active_nodes,
+            active_inputs: *active_inputs,
+        })
+        .collect();
+}
+
+#[test]
+fn sorts_into_fronts() {
+    let objectives = objectives(&[
+        (0.1, 10, 3),  // front 0
+        (0.2, 5, 3),   // front 0
+        (0.2, 10, 3),  // dominated by 0 and 1
+        (0.3, 2, 1),   // front 0
+        (0.3, 10, 4),  // dominated by 2
+        (0.1, 10, 3),  // same as 0, front 0
+        (f32::NAN, 1, 1),  // NaN is the worst error, but the smallest graph
+    ]);
+
+    let mut fronts = non_dominated_sort(&objectives);
+    fronts.iter_mut().for_each(|front| front.sort());
+    assert_eq!(fronts, vec![vec![0, 1, 3, 5, 6], vec![2], vec![4]]);
+}
+
+#[test]
+fn crowded_solutions_are_dropped() {
+    let objectives = objectives(&[
+        (0.1, 10, 3),
+        (0.2, 6, 3),
+        (0.21, 5, 3),  // close to 1
+        (0.3, 3, 3),
+        (0.4, 1, 3),
+        (0.5, 20, 5),  // dominated
+    ]);
+
+    let distances = crowding_distances(&objectives, &[0, 1, 2, 3, 4]);
+    assert_eq!(distances[0], f32::INFINITY);
+    assert_eq!(distances[4], f32::INFINITY);
+    assert!(distances[2] < distances[1] && distances[2] < distances[3]);
+
+    let mut survivors = nsga2_survivors(&objectives, 4);
+    survivors.sort();
+    assert_eq!(survivors, vec![0, 1, 3, 4]);
+}
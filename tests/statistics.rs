@@ -0,0 +1,70 @@
+use cgp::analysis::statistics::{holm_correction, mann_whitney_u, wilcoxon_signed_rank};
+
+// reference values of scipy.stats.mannwhitneyu(first, second, method="asymptotic") and
+// scipy.stats.wilcoxon(first, second, correction=True, method="approx"); scipy reports min(W+, W-) as statistic
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!((actual - expected).abs() < 1e-9, "{} != {}", actual, expected);
+}
+
+#[test]
+fn mann_whitney_u_matches_reference() {
+    // example of the scipy documentation
+    let result = mann_whitney_u(&[19., 22., 16., 29., 24.], &[20., 11., 17., 12.]).unwrap();
+    assert_eq!(result.u, 17.);
+    assert_close(result.p_value, 0.11134688653314041);
+    assert_close(result.a12, 0.85);
+}
+
+#[test]
+fn mann_whitney_u_with_ties_matches_reference() {
+    let result = mann_whitney_u(&[1., 2., 2., 3., 5., 5., 7.], &[2., 3., 3., 4., 6., 8.]).unwrap();
+    assert_eq!(result.u, 16.);
+    assert_close(result.p_value, 0.5150755147479537);
+    assert_close(result.a12, 16. / 42.);
+}
+
+#[test]
+fn mann_whitney_u_of_equal_values() {
+    let result = mann_whitney_u(&[1., 1.], &[1., 1., 1.]).unwrap();
+    assert_eq!(result.u, 3.);
+    assert_eq!(result.p_value, 1.);
+    assert_eq!(result.a12, 0.5);
+    assert!(mann_whitney_u(&[], &[1.]).is_none());
+}
+
+#[test]
+fn wilcoxon_signed_rank_matches_reference() {
+    // Darwin's data of the scipy documentation, W- = 24
+    let differences = [6., 8., 14., 16., 23., 24., 28., 29., 41., -48., 49., 56., 60., -67., 75.];
+    let result = wilcoxon_signed_rank(&differences, &[0.; 15]).unwrap();
+    assert_eq!(result.w_plus, 96.);
+    assert_eq!(result.n, 15);
+    assert_close(result.p_value, 0.043772323763041195);
+    assert_close(result.rank_biserial, 0.6);
+}
+
+#[test]
+fn wilcoxon_signed_rank_with_ties_and_zero_differences_matches_reference() {
+    // differences 0, 1, -1, 2, 2, 0, 2, 1: the zeros are dropped, the absolute differences are tied
+    let result = wilcoxon_signed_rank(&[1.5, 2., 3., 4., 5., 6., 7., 8.], &[1.5, 1., 4., 2., 3., 6., 5., 7.]).unwrap();
+    assert_eq!(result.w_plus, 19.);
+    assert_eq!(result.n, 6);
+    assert_close(result.p_value, 0.0862755695825295);
+    assert_close(result.rank_biserial, 17. / 21.);
+
+    assert!(wilcoxon_signed_rank(&[1., 2.], &[1., 2.]).is_none());
+}
+
+#[test]
+fn holm_correction_matches_reference() {
+    // statsmodels.stats.multitest.multipletests(p_values, method="holm")
+    let adjusted = holm_correction(&[0.01, 0.04, 0.03, 0.005, 0.04, 0.5]);
+    let expected = [0.05, 0.12, 0.12, 0.03, 0.12, 0.5];
+    for (actual, expected) in adjusted.iter().zip(expected.iter()) {
+        assert_close(*actual, *expected);
+    }
+
+    assert_eq!(holm_correction(&[0.6, 0.7]), vec![1., 1.]);
+    assert!(holm_correction(&[]).is_empty());
+}
@@ -0,0 +1,167 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use cgp::datasets::fitness_metric_type::FitnessFuncType;
+use cgp::global_params::CgpParameters;
+use cgp::utils::connection_sampling::ConnectionSampling;
+use cgp::utils::multiclass_decoding::MulticlassDecoding;
+use cgp::utils::runner::Runner;
+use cgp::utils::stopping_criteria::{StopReason, StoppingCriteria};
+use serde_json::Value;
+
+const NO_CRITERIA: StoppingCriteria = StoppingCriteria {
+    max_iterations: None,
+    max_fitness_evaluations: None,
+    max_seconds: None,
+    target_fitness: None,
+    stagnation: None,
+};
+const NO_TIME: Duration = Duration::ZERO;
+
+fn runner() -> Runner {
+    let params = CgpParameters {
+        graph_width: 20,
+        rows: 1,
+        levels_back: None,
+        mu: 1,
+        lambda: 4,
+        eval_after_iterations: 0,
+        nbr_inputs: 3,
+        nbr_outputs: 1,
+        nbr_classes: 2,
+        fitness_func_type: FitnessFuncType::ClassificationBinary,
+        connection_sampling: ConnectionSampling::Uniform,
+        multiclass_decoding: MulticlassDecoding::Argmax,
+        class_costs: None,
+    };
+    let mut rng = ChaCha8Rng::seed_from_u64(0);
+    let data: Vec<Vec<f32>> = (0..40).map(|_| (0..3).map(|_| rng.gen_range(-1.0..1.0)).collect()).collect();
+    let labels: Vec<usize> = data.iter().map(|sample| (sample[0] > sample[1]) as usize).collect();
+    return Runner::new(params, data, labels, None, None, 0);
+}
+
+#[test]
+fn without_criteria_runs_never_stop() {
+    let mut runner = runner();
+    for _ in 0..20 {
+        runner.learn_step();
+        assert_eq!(NO_CRITERIA.check(&runner, Duration::from_secs(1_000_000)), None);
+    }
+}
+
+#[test]
+fn stops_after_max_iterations() {
+    let criteria = StoppingCriteria { max_iterations: Some(3), ..NO_CRITERIA };
+    let mut runner = runner();
+    for _ in 0..2 {
+        runner.learn_step();
+        assert_eq!(criteria.check(&runner, NO_TIME), None);
+    }
+    runner.learn_step();
+    assert_eq!(criteria.check(&runner, NO_TIME), Some(StopReason::MaxIterations));
+}
+
+#[test]
+fn stops_after_max_fitness_evaluations() {
+    let mut runner = runner();
+    // the initial population is evaluated, every iteration evaluates lambda offspring
+    assert_eq!(runner.get_nbr_fitness_evaluations(), 5);
+    let criteria = StoppingCriteria { max_fitness_evaluations: Some(13), ..NO_CRITERIA };
+    assert_eq!(criteria.check(&runner, NO_TIME), None);
+    runner.learn_step();
+    assert_eq!(criteria.check(&runner, NO_TIME), None);
+    runner.learn_step();
+    assert_eq!(runner.get_nbr_fitness_evaluations(), 13);
+    assert_eq!(criteria.check(&runner, NO_TIME), Some(StopReason::MaxFitnessEvaluations));
+}
+
+#[test]
+fn stops_after_the_time_budget() {
+    let criteria = StoppingCriteria { max_seconds: Some(2.5), ..NO_CRITERIA };
+    let runner = runner();
+    assert_eq!(criteria.check(&runner, Duration::from_secs(2)), None);
+    assert_eq!(criteria.check(&runner, Duration::from_millis(2500)), Some(StopReason::TimeBudget));
+}
+
+#[test]
+fn stops_at_the_target_fitness() {
+    let runner = runner();
+    let error = runner.get_parent_error();
+    assert!(error > 0.);
+
+    let below = StoppingCriteria { target_fitness: Some(error * 0.99), ..NO_CRITERIA };
+    assert_eq!(below.check(&runner, NO_TIME), None);
+    let reached = StoppingCriteria { target_fitness: Some(error), ..NO_CRITERIA };
+    assert_eq!(reached.check(&runner, NO_TIME), Some(StopReason::TargetFitness));
+}
+
+#[test]
+fn stops_on_stagnation() {
+    let mut runner = runner();
+    while runner.get_iterations_without_improvement() < 3 {
+        runner.learn_step();
+        assert!(runner.get_iteration() < 1000, "the fitness keeps improving");
+    }
+    let without_improvement = runner.get_iterations_without_improvement();
+
+    let longer = StoppingCriteria { stagnation: Some(without_improvement + 1), ..NO_CRITERIA };
+    assert_eq!(longer.check(&runner, NO_TIME), None);
+    let reached = StoppingCriteria { stagnation: Some(without_improvement), ..NO_CRITERIA };
+    assert_eq!(reached.check(&runner, NO_TIME), Some(StopReason::Stagnation));
+}
+
+#[test]
+fn combined_criteria_stop_at_the_first_one_met() {
+    let mut runner = runner();
+    let error = runner.get_parent_error();
+    let criteria = StoppingCriteria {
+        max_iterations: Some(4),
+        max_fitness_evaluations: Some(1_000),
+        max_seconds: Some(60.),
+        target_fitness: Some(error * 0.5),
+        stagnation: None,
+    };
+    assert_eq!(criteria.check(&runner, NO_TIME), None);
+    assert_eq!(criteria.check(&runner, Duration::from_secs(60)), Some(StopReason::TimeBudget));
+
+    for _ in 0..4 {
+        runner.learn_step();
+    }
+    assert_eq!(criteria.check(&runner, NO_TIME), Some(StopReason::MaxIterations));
+
+    // if several criteria are met, the target fitness is reported first
+    let reached = StoppingCriteria { target_fitness: Some(runner.get_parent_error()), ..criteria };
+    assert_eq!(reached.check(&runner, NO_TIME), Some(StopReason::TargetFitness));
+
+    // the default stops after 100_000 iterations or at an error of 0.01
+    let default = StoppingCriteria::default();
+    assert_eq!((default.max_iterations, default.target_fitness), (Some(100_000), Some(0.01)));
+}
+
+#[test]
+fn folds_run_at_least_one_iteration() {
+    let dir = std::env::temp_dir().join(format!("cgp_stopping_criteria_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    // every error is at most 1, so the target is met before the first iteration
+    let dataset_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/datasets/data/breast+cancer+wisconsin+diagnostic.data");
+    let output = Command::new(env!("CARGO_BIN_EXE_cgp"))
+        .current_dir(&dir)
+        .args(["--dataset", "3", "--nbr-nodes", "20", "--seed", "1", "--target-fitness", "1"])
+        .arg("--dataset-path")
+        .arg(&dataset_path)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let nodes_path = dir.join("Experiments_Output/breast_cancer/redundancy_type_2/databloat_20/number_nodes_20");
+    let save_path = fs::read_dir(nodes_path).unwrap().next().unwrap().unwrap().path();
+    let summary: Value = serde_json::from_str(&fs::read_to_string(save_path.join("run_0_summary.json")).unwrap()).unwrap();
+    assert_eq!(summary["iterations"], 1);
+    assert_eq!(summary["stop_reason"], "target_fitness");
+    fs::remove_dir_all(&dir).unwrap();
+}
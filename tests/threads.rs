@@ -0,0 +1,67 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use serde_json::Value;
+
+const DATASET: &str = "src/datasets/data/breast+cancer+wisconsin+diagnostic.data";
+const NBR_FOLDS: usize = 5;
+
+/// Runs the binary in an empty directory and returns the summaries of all folds without their run time.
+fn run(name: &str, threads: usize, extra_args: &[&str]) -> Vec<Value> {
+    let dir = std::env::temp_dir().join(format!("cgp_threads_{}_{}_{}", std::process::id(), name, threads));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    let dataset_path = Path::new(env!("CARGO_MANIFEST_DIR")).join(DATASET);
+    let output = Command::new(env!("CARGO_BIN_EXE_cgp"))
+        .current_dir(&dir)
+        .args(["--dataset", "3", "--nbr-nodes", "30", "--seed", "11", "--max-iterations", "200"])
+        .arg("--dataset-path")
+        .arg(&dataset_path)
+        .args(["--threads", &threads.to_string()])
+        .args(extra_args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    // the only settings directory of the run
+    let save_path: PathBuf = fs::read_dir(dir.join("Experiments_Output/breast_cancer/redundancy_type_2/databloat_20/number_nodes_30"))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap()
+        .path();
+    let summaries = (0..NBR_FOLDS)
+        .map(|run_id| {
+            let contents = fs::read_to_string(save_path.join(format!("run_{}_summary.json", run_id))).unwrap();
+            let mut summary: Value = serde_json::from_str(&contents).unwrap();
+            summary.as_object_mut().unwrap().remove("elapsed_seconds");
+            summary
+        })
+        .collect();
+    fs::remove_dir_all(&dir).unwrap();
+    return summaries;
+}
+
+/// The final parents, their expressions and fitness must not depend on how folds and offspring are scheduled.
+fn check_threads(name: &str, extra_args: &[&str]) {
+    let single_threaded = run(name, 1, extra_args);
+    let multi_threaded = run(name, 4, extra_args);
+
+    for (single, multi) in single_threaded.iter().zip(multi_threaded.iter()) {
+        assert_eq!(single["fitness_train"], multi["fitness_train"]);
+        assert_eq!(single["fitness_eval"], multi["fitness_eval"]);
+        assert_eq!(single["active_nodes"], multi["active_nodes"]);
+        assert_eq!(single, multi);
+    }
+}
+
+#[test]
+fn single_and_multi_threaded_runs_are_equal() {
+    check_threads("fitness", &[]);
+}
+
+#[test]
+fn single_and_multi_threaded_nsga2_runs_are_equal() {
+    check_threads("nsga2", &["--selection", "nsga2", "--nsga2-population", "8", "--mutation", "point", "--mutation-rate", "0.05"]);
+}